@@ -0,0 +1,331 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use image::RgbaImage;
+
+use render::{load_images, RenderError};
+use {Map, MapLayer, TileGid};
+
+/// Options for `pack_atlas`.
+#[derive(Clone, Debug)]
+pub struct AtlasOptions {
+    /// Maximum width and height of an atlas page.
+    pub page_size: u32,
+    /// Number of times the border pixels of each tile are repeated around it,
+    /// so that filtering does not sample neighbouring tiles.
+    pub extrude: u32,
+    /// Transparent pixels between the extruded tiles.
+    pub padding: u32,
+}
+
+impl Default for AtlasOptions {
+    fn default() -> AtlasOptions {
+        AtlasOptions {
+            page_size: 2048,
+            extrude: 1,
+            padding: 1,
+        }
+    }
+}
+
+/// The position of a tile in an atlas, without its extruded border.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasRect {
+    /// Index into `Atlas::pages`.
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Tile images packed into one or more textures.
+#[derive(Debug)]
+pub struct Atlas {
+    pub pages: Vec<RgbaImage>,
+    /// Position of each packed tile, by global tile id without flags. Tiles
+    /// are stored unflipped, flips have to be applied when drawing them.
+    pub rects: HashMap<u32, AtlasRect>,
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Image(RenderError),
+    /// A tile does not fit on a page, even with nothing else on it.
+    TileTooLarge {
+        gid: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AtlasError::Image(ref e) => e.fmt(f),
+            AtlasError::TileTooLarge { gid, width, height } => write!(
+                f,
+                "tile {} of size {}x{} does not fit on an atlas page",
+                gid, width, height
+            ),
+        }
+    }
+}
+
+impl Error for AtlasError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            AtlasError::Image(ref e) => Some(e),
+            AtlasError::TileTooLarge { .. } => None,
+        }
+    }
+}
+
+/// A tile to be packed, identified by its image and the rectangle in it.
+/// Tilesets that share an image share their packed tiles.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Source<'m> {
+    image: &'m str,
+    rect: (u32, u32, u32, u32),
+}
+
+/// A row of tiles on a page, filled from left to right.
+struct Shelf {
+    y: u32,
+    height: u32,
+    used_width: u32,
+}
+
+struct Page {
+    shelves: Vec<Shelf>,
+    used_width: u32,
+    used_height: u32,
+}
+
+/// Packs the images of all tiles used by the tile layers and tile objects of
+/// `map` into atlas pages. Image sources are relative to `base_dir`, which
+/// usually is the directory containing the map file.
+///
+/// Pages are cropped to the area that is actually used, so they can be
+/// smaller than `AtlasOptions::page_size`.
+pub fn pack_atlas<P: AsRef<Path>>(
+    map: &Map,
+    base_dir: P,
+    options: &AtlasOptions,
+) -> Result<Atlas, AtlasError> {
+    let mut sources = HashMap::new();
+
+    for gid in used_gids(map) {
+        let source = map
+            .tileset_for_gid(TileGid(gid))
+            .and_then(|(tileset, local_id)| {
+                Some(Source {
+                    image: &tileset.tile_image(local_id)?.source,
+                    rect: tileset.tile_rect(local_id)?,
+                })
+            });
+
+        if let Some(source) = source {
+            sources.insert(gid, source);
+        }
+    }
+
+    let images = load_images(
+        map.tilesets
+            .iter()
+            .flat_map(|t| {
+                t.image
+                    .iter()
+                    .chain(t.tiles.values().filter_map(|tile| tile.image.as_ref()))
+            })
+            .filter(|i| sources.values().any(|s| s.image == i.source)),
+        base_dir.as_ref(),
+    )
+    .map_err(AtlasError::Image)?;
+
+    // tiles of tilesets sharing an image are only packed once
+    let mut unique: HashMap<&Source, u32> = HashMap::new();
+    for (&gid, source) in &sources {
+        let first = unique.entry(source).or_insert(gid);
+        *first = (*first).min(gid);
+    }
+
+    // place the tallest tiles first, which keeps shelves tightly filled
+    let mut unique: Vec<(&Source, u32)> = unique.into_iter().collect();
+    unique.sort_by_key(|&(s, gid)| (Reverse(s.rect.3), Reverse(s.rect.2), gid));
+
+    let border = options.extrude * 2 + options.padding;
+    let mut pages: Vec<Page> = Vec::new();
+    let mut placed = HashMap::new();
+
+    for (source, gid) in unique {
+        let (_, _, width, height) = source.rect;
+        let (outer_width, outer_height) = (width + border, height + border);
+
+        if outer_width > options.page_size || outer_height > options.page_size {
+            return Err(AtlasError::TileTooLarge {
+                gid: gid,
+                width: width,
+                height: height,
+            });
+        }
+
+        let position = pages
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, page)| {
+                page.place(outer_width, outer_height, options.page_size)
+                    .map(|(x, y)| (i, x, y))
+            })
+            .next();
+
+        let (page, x, y) = match position {
+            Some(p) => p,
+            None => {
+                let mut page = Page {
+                    shelves: Vec::new(),
+                    used_width: 0,
+                    used_height: 0,
+                };
+                let (x, y) = page
+                    .place(outer_width, outer_height, options.page_size)
+                    .expect("tile fits on an empty page");
+                pages.push(page);
+                (pages.len() - 1, x, y)
+            }
+        };
+
+        placed.insert(
+            source.clone(),
+            AtlasRect {
+                page: page,
+                x: x + options.extrude,
+                y: y + options.extrude,
+                width: width,
+                height: height,
+            },
+        );
+    }
+
+    let mut page_images: Vec<RgbaImage> = pages
+        .iter()
+        .map(|p| RgbaImage::new(p.used_width, p.used_height))
+        .collect();
+
+    for (source, rect) in &placed {
+        if let Some(image) = images.get(source.image) {
+            blit_extruded(
+                &mut page_images[rect.page],
+                image,
+                source.rect,
+                rect,
+                options.extrude,
+            );
+        }
+    }
+
+    Ok(Atlas {
+        pages: page_images,
+        rects: sources
+            .iter()
+            .map(|(&gid, source)| (gid, placed[source]))
+            .collect(),
+    })
+}
+
+/// Returns the global ids of all tiles used by the map, without flags.
+fn used_gids(map: &Map) -> BTreeSet<u32> {
+    let mut gids = BTreeSet::new();
+
+    for layer in map.all_layers() {
+        match *layer {
+            MapLayer::Layer(ref l) => {
+                gids.extend(l.data.tile_gids.iter().map(|&gid| TileGid(gid).gid()));
+            }
+            MapLayer::ObjectGroup(ref o) => {
+                gids.extend(
+                    o.objects
+                        .iter()
+                        .filter_map(|o| o.gid)
+                        .map(|gid| TileGid(gid).gid()),
+                );
+            }
+            MapLayer::ImageLayer(_) | MapLayer::Group(_) => {}
+        }
+    }
+
+    gids.remove(&0);
+    gids
+}
+
+impl Page {
+    /// Finds room for a rectangle with the given size, and returns its
+    /// top-left corner.
+    fn place(&mut self, width: u32, height: u32, page_size: u32) -> Option<(u32, u32)> {
+        let mut position = None;
+
+        for shelf in &mut self.shelves {
+            if height <= shelf.height && shelf.used_width + width <= page_size {
+                position = Some((shelf.used_width, shelf.y));
+                shelf.used_width += width;
+                break;
+            }
+        }
+
+        if position.is_none() {
+            let y = self.shelves.last().map_or(0, |s| s.y + s.height);
+            if y + height > page_size {
+                return None;
+            }
+
+            self.shelves.push(Shelf {
+                y: y,
+                height: height,
+                used_width: width,
+            });
+            position = Some((0, y));
+        }
+
+        if let Some((x, y)) = position {
+            self.used_width = self.used_width.max(x + width);
+            self.used_height = self.used_height.max(y + height);
+        }
+
+        position
+    }
+}
+
+/// Copies a rectangle of `image` to `rect` on the page, repeating its border
+/// pixels `extrude` times around it.
+fn blit_extruded(
+    page: &mut RgbaImage,
+    image: &RgbaImage,
+    (src_x, src_y, width, height): (u32, u32, u32, u32),
+    rect: &AtlasRect,
+    extrude: u32,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let extrude = extrude as i64;
+
+    for dy in -extrude..height as i64 + extrude {
+        for dx in -extrude..width as i64 + extrude {
+            // clamp to the tile, which repeats its border
+            let sx = src_x + dx.max(0).min(width as i64 - 1) as u32;
+            let sy = src_y + dy.max(0).min(height as i64 - 1) as u32;
+
+            if sx < image.width() && sy < image.height() {
+                page.put_pixel(
+                    (rect.x as i64 + dx) as u32,
+                    (rect.y as i64 + dy) as u32,
+                    *image.get_pixel(sx, sy),
+                );
+            }
+        }
+    }
+}
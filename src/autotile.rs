@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use {Layer, Tileset, WangId, WangSet, WangTile};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AutotileError {
+    /// The number of given colors does not match the size of the layer.
+    SizeMismatch { expected: usize, actual: usize },
+    /// The wang set does not contain any tiles to choose from.
+    EmptyWangSet,
+}
+
+impl fmt::Display for AutotileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AutotileError::SizeMismatch { expected, actual } => write!(
+                f,
+                "expected {} colors for the layer, got {}",
+                expected, actual
+            ),
+            AutotileError::EmptyWangSet => write!(f, "wang set does not contain any tiles"),
+        }
+    }
+}
+
+impl Error for AutotileError {}
+
+// Offsets of the cells touching each position of a `WangId`, besides the
+// cell itself.
+const TOUCHING: [&[(i64, i64)]; 8] = [
+    &[(0, -1)],
+    &[(1, 0), (0, -1), (1, -1)],
+    &[(1, 0)],
+    &[(1, 0), (0, 1), (1, 1)],
+    &[(0, 1)],
+    &[(-1, 0), (0, 1), (-1, 1)],
+    &[(-1, 0)],
+    &[(-1, 0), (0, -1), (-1, -1)],
+];
+
+/// Paints `layer` with tiles from `wangset`, given one terrain color per cell
+/// (in row-major order). Colors are indices into `wangset.colors` as used by
+/// `WangId`, `0` leaves a cell empty.
+///
+/// Edges and corners shared by cells of different colors get the color with
+/// the lower index, so background terrains should come first in the wang
+/// set. Since neighbouring cells agree on shared edges and corners, the
+/// chosen tiles always fit together, as long as the wang set is complete. If
+/// it is not, the tiles with the fewest mismatching edges and corners are
+/// used instead.
+///
+/// When several tiles fit, one is chosen at random, weighted by the
+/// probability of the tile and of its colors. `rng` must return uniformly
+/// distributed values in `[0, 1)`.
+pub fn autotile<R: FnMut() -> f32>(
+    layer: &mut Layer,
+    tileset: &Tileset,
+    wangset: &WangSet,
+    colors: &[u8],
+    mut rng: R,
+) -> Result<(), AutotileError> {
+    let (width, height) = (layer.width as i64, layer.height as i64);
+
+    let expected = (width * height) as usize;
+    if colors.len() != expected {
+        return Err(AutotileError::SizeMismatch {
+            expected: expected,
+            actual: colors.len(),
+        });
+    }
+
+    if wangset.tiles.is_empty() {
+        return Err(AutotileError::EmptyWangSet);
+    }
+
+    let mut by_id: HashMap<WangId, Vec<&WangTile>> = HashMap::new();
+    for tile in &wangset.tiles {
+        by_id
+            .entry(tile.wangid.masked(wangset.type_))
+            .or_insert_with(Vec::new)
+            .push(tile);
+    }
+
+    let color_at = |x: i64, y: i64| {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            0
+        } else {
+            colors[(y * width + x) as usize]
+        }
+    };
+
+    let mut gids = Vec::with_capacity(expected);
+
+    for y in 0..height {
+        for x in 0..width {
+            let own = color_at(x, y);
+            if own == 0 {
+                gids.push(0);
+                continue;
+            }
+
+            let mut wanted = WangId::default();
+            for (pos, touching) in TOUCHING.iter().enumerate() {
+                wanted.0[pos] = touching
+                    .iter()
+                    .map(|&(dx, dy)| color_at(x + dx, y + dy))
+                    .filter(|&c| c != 0)
+                    .fold(own, ::std::cmp::min);
+            }
+            let wanted = wanted.masked(wangset.type_);
+
+            let candidates = match by_id.get(&wanted) {
+                Some(tiles) => tiles.clone(),
+                None => closest_tiles(&wanted, wangset),
+            };
+
+            let tile = choose_weighted(&candidates, tileset, wangset, &mut rng);
+            gids.push(tileset.firstgid + tile.tileid);
+        }
+    }
+
+    layer.data.tile_gids = gids;
+
+    Ok(())
+}
+
+fn closest_tiles<'a>(wanted: &WangId, wangset: &'a WangSet) -> Vec<&'a WangTile> {
+    let mismatches = |tile: &WangTile| {
+        let tile_id = tile.wangid.masked(wangset.type_);
+        (0..8).filter(|&i| tile_id.0[i] != wanted.0[i]).count()
+    };
+
+    let best = wangset.tiles.iter().map(&mismatches).min().unwrap_or(0);
+
+    wangset
+        .tiles
+        .iter()
+        .filter(|t| mismatches(t) == best)
+        .collect()
+}
+
+fn choose_weighted<'a, R: FnMut() -> f32>(
+    candidates: &[&'a WangTile],
+    tileset: &Tileset,
+    wangset: &WangSet,
+    rng: &mut R,
+) -> &'a WangTile {
+    let weight = |tile: &WangTile| {
+        let tile_probability = tileset.tile(tile.tileid).map_or(1.0, |t| t.probability);
+
+        tile.wangid
+            .masked(wangset.type_)
+            .0
+            .iter()
+            .filter_map(|&c| wangset.color(c))
+            .fold(tile_probability, |p, color| p * color.probability)
+    };
+
+    let weights: Vec<f32> = candidates.iter().map(|t| weight(t)).collect();
+    let total: f32 = weights.iter().sum();
+
+    if candidates.len() == 1 || total <= 0.0 {
+        return candidates[0];
+    }
+
+    let mut choice = rng() * total;
+    for (tile, weight) in candidates.iter().zip(weights) {
+        if choice < weight {
+            return tile;
+        }
+        choice -= weight;
+    }
+
+    candidates[candidates.len() - 1]
+}
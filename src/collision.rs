@@ -0,0 +1,131 @@
+use {Layer, Map, MapLayer, Tileset};
+
+/// A grid of solid and empty cells, stored as a bitset in row-major order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollisionGrid {
+    pub width: u32,
+    pub height: u32,
+    bits: Vec<u64>,
+}
+
+impl CollisionGrid {
+    /// Creates a grid in which all cells are empty.
+    pub fn new(width: u32, height: u32) -> CollisionGrid {
+        let cells = width as usize * height as usize;
+
+        CollisionGrid {
+            width: width,
+            height: height,
+            bits: vec![0; (cells + 63) / 64],
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the cell at `(x, y)` is solid. Cells outside of the grid are
+    /// empty.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.index(x, y)
+            .map_or(false, |i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+    }
+
+    /// Sets whether the cell at `(x, y)` is solid. Cells outside of the grid
+    /// are ignored.
+    pub fn set(&mut self, x: u32, y: u32, solid: bool) {
+        if let Some(i) = self.index(x, y) {
+            if solid {
+                self.bits[i / 64] |= 1 << (i % 64);
+            } else {
+                self.bits[i / 64] &= !(1 << (i % 64));
+            }
+        }
+    }
+
+    /// Covers all solid cells with non-overlapping rectangles
+    /// `(x, y, width, height)`, in cells.
+    ///
+    /// Rectangles are grown greedily, first along rows and then down, which
+    /// keeps their number small for typical level geometry, but does not
+    /// guarantee the minimum.
+    pub fn merge_rects(&self) -> Vec<(u32, u32, u32, u32)> {
+        let mut covered = CollisionGrid::new(self.width, self.height);
+        let mut rects = Vec::new();
+
+        let free = |covered: &CollisionGrid, x, y| self.get(x, y) && !covered.get(x, y);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !free(&covered, x, y) {
+                    continue;
+                }
+
+                let mut width = 1;
+                while free(&covered, x + width, y) {
+                    width += 1;
+                }
+
+                let mut height = 1;
+                while (x..x + width).all(|x| free(&covered, x, y + height)) {
+                    height += 1;
+                }
+
+                for cy in y..y + height {
+                    for cx in x..x + width {
+                        covered.set(cx, cy, true);
+                    }
+                }
+
+                rects.push((x, y, width, height));
+            }
+        }
+
+        rects
+    }
+}
+
+impl Map {
+    /// Builds a grid of the size of the map, in which a cell is solid if any
+    /// tile layer accepted by `layer_filter` has a tile there for which
+    /// `predicate` returns `true`. The predicate gets the tileset and the
+    /// local id of a tile, so it can check its properties or collision shapes
+    /// (see `Tileset::tile`).
+    pub fn collision_grid<F, P>(&self, mut layer_filter: F, mut predicate: P) -> CollisionGrid
+    where
+        F: FnMut(&Layer) -> bool,
+        P: FnMut(&Tileset, u32) -> bool,
+    {
+        let mut grid = CollisionGrid::new(self.width, self.height);
+
+        for layer in self.all_layers() {
+            let layer = match *layer {
+                MapLayer::Layer(ref l) if layer_filter(l) => l,
+                _ => continue,
+            };
+
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if grid.get(x, y) {
+                        continue;
+                    }
+
+                    let solid = layer
+                        .tile_gid(x, y)
+                        .and_then(|gid| self.tileset_for_gid(gid))
+                        .map_or(false, |(tileset, local_id)| predicate(tileset, local_id));
+
+                    if solid {
+                        grid.set(x, y, true);
+                    }
+                }
+            }
+        }
+
+        grid
+    }
+}
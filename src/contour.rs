@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use CollisionGrid;
+
+/// The outline of a connected solid region of a `CollisionGrid`, with the
+/// outlines of the empty regions enclosed by it.
+///
+/// Outlines run clockwise and holes counter-clockwise, as seen on screen with
+/// the y axis pointing down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contour {
+    pub outline: Vec<(f32, f32)>,
+    pub holes: Vec<Vec<(f32, f32)>>,
+}
+
+/// A boundary edge between a solid and an empty cell, of length 1. The solid
+/// cell is on its right.
+#[derive(Clone, Copy)]
+struct Edge {
+    start: (i32, i32),
+    direction: (i32, i32),
+}
+
+impl Edge {
+    fn end(&self) -> (i32, i32) {
+        (
+            self.start.0 + self.direction.0,
+            self.start.1 + self.direction.1,
+        )
+    }
+
+    /// The solid cell on the right of the edge.
+    fn solid_cell(&self) -> (i32, i32) {
+        let (x, y) = self.start;
+
+        match self.direction {
+            (1, 0) => (x, y),
+            (0, 1) => (x - 1, y),
+            (-1, 0) => (x - 1, y - 1),
+            _ => (x, y - 1),
+        }
+    }
+}
+
+impl CollisionGrid {
+    /// Traces the outlines of all solid regions, with corners where the
+    /// outlines change direction. Cells are scaled to the given size, so
+    /// the points can be in pixels.
+    ///
+    /// Cells that only touch diagonally are not connected, so their outlines
+    /// are traced separately.
+    pub fn contours(&self, cell_width: f32, cell_height: f32) -> Vec<Contour> {
+        let solid = |x: i32, y: i32| x >= 0 && y >= 0 && self.get(x as u32, y as u32);
+
+        let mut edges = Vec::new();
+
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                if !solid(x, y) {
+                    continue;
+                }
+
+                // clockwise around the cell
+                let sides = [
+                    ((x, y - 1), (x, y), (1, 0)),
+                    ((x + 1, y), (x + 1, y), (0, 1)),
+                    ((x, y + 1), (x + 1, y + 1), (-1, 0)),
+                    ((x - 1, y), (x, y + 1), (0, -1)),
+                ];
+
+                for &((nx, ny), start, direction) in sides.iter() {
+                    if !solid(nx, ny) {
+                        edges.push(Edge {
+                            start: start,
+                            direction: direction,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut outgoing: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (i, edge) in edges.iter().enumerate() {
+            outgoing.entry(edge.start).or_insert_with(Vec::new).push(i);
+        }
+
+        let mut used = vec![false; edges.len()];
+        let mut outlines = Vec::new();
+        let mut holes = Vec::new();
+
+        for first in 0..edges.len() {
+            if used[first] {
+                continue;
+            }
+
+            let mut corners = Vec::new();
+            let mut current = first;
+
+            loop {
+                used[current] = true;
+                let edge = edges[current];
+                let next = next_edge(&edges, &outgoing[&edge.end()], edge.direction);
+
+                if edges[next].direction != edge.direction {
+                    corners.push(edge.end());
+                }
+
+                if next == first {
+                    break;
+                }
+                current = next;
+            }
+
+            let points: Vec<(f32, f32)> = corners
+                .iter()
+                .map(|&(x, y)| (x as f32 * cell_width, y as f32 * cell_height))
+                .collect();
+
+            if signed_area(&corners) > 0 {
+                outlines.push((corners, points));
+            } else {
+                holes.push((edges[first].solid_cell(), points));
+            }
+        }
+
+        let mut contours: Vec<Contour> = outlines
+            .iter()
+            .map(|&(_, ref points)| Contour {
+                outline: points.clone(),
+                holes: Vec::new(),
+            })
+            .collect();
+
+        // a hole belongs to the smallest outline around the solid cells that
+        // border it
+        for ((x, y), points) in holes {
+            let center = (x as f32 + 0.5, y as f32 + 0.5);
+
+            let owner = outlines
+                .iter()
+                .enumerate()
+                .filter(|&(_, &(ref corners, _))| contains(corners, center))
+                .min_by_key(|&(_, &(ref corners, _))| signed_area(corners))
+                .map(|(i, _)| i);
+
+            if let Some(i) = owner {
+                contours[i].holes.push(points);
+            }
+        }
+
+        contours
+    }
+}
+
+/// Chooses how to continue from a vertex, turning right if possible. This
+/// keeps the solid cells on the right close, so diagonal neighbours are not
+/// connected.
+fn next_edge(edges: &[Edge], candidates: &[usize], (dx, dy): (i32, i32)) -> usize {
+    let preferred = [(-dy, dx), (dx, dy), (dy, -dx)];
+
+    preferred
+        .iter()
+        .filter_map(|&direction| {
+            candidates
+                .iter()
+                .cloned()
+                .find(|&i| edges[i].direction == direction)
+        })
+        .next()
+        .expect("boundary edges form closed loops")
+}
+
+/// Twice the area enclosed by the points, positive if they run clockwise on
+/// screen.
+fn signed_area(points: &[(i32, i32)]) -> i64 {
+    (0..points.len())
+        .map(|i| {
+            let (x0, y0) = points[i];
+            let (x1, y1) = points[(i + 1) % points.len()];
+            x0 as i64 * y1 as i64 - x1 as i64 * y0 as i64
+        })
+        .sum()
+}
+
+fn contains(polygon: &[(i32, i32)], (x, y): (f32, f32)) -> bool {
+    let mut inside = false;
+
+    for i in 0..polygon.len() {
+        let (x0, y0) = (polygon[i].0 as f32, polygon[i].1 as f32);
+        let j = (i + 1) % polygon.len();
+        let (x1, y1) = (polygon[j].0 as f32, polygon[j].1 as f32);
+
+        if (y0 > y) != (y1 > y) && x < x0 + (y - y0) / (y1 - y0) * (x1 - x0) {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
@@ -0,0 +1,263 @@
+use {Map, Orientation, StaggerAxis, StaggerIndex};
+
+/// Layout parameters of staggered and hexagonal maps, computed like tiled
+/// does for rendering. Staggered maps are treated as hexagonal maps with a
+/// side length of `0`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StaggerParams {
+    pub tile_width: i32,
+    pub tile_height: i32,
+    pub side_length_x: i32,
+    pub side_length_y: i32,
+    pub side_offset_x: i32,
+    pub side_offset_y: i32,
+    pub column_width: i32,
+    pub row_height: i32,
+    pub stagger_x: bool,
+    pub stagger_even: bool,
+}
+
+impl StaggerParams {
+    pub fn new(map: &Map) -> StaggerParams {
+        let tile_width = map.tilewidth as i32 & !1;
+        let tile_height = map.tileheight as i32 & !1;
+
+        let stagger_x = map.staggeraxis == Some(StaggerAxis::X);
+        let stagger_even = map.staggerindex == Some(StaggerIndex::Even);
+
+        let side_length = match map.orientation {
+            Orientation::Hexagonal => map.hexsidelength.unwrap_or(0),
+            _ => 0,
+        };
+
+        let side_length_x = if stagger_x { side_length } else { 0 };
+        let side_length_y = if stagger_x { 0 } else { side_length };
+
+        let side_offset_x = (tile_width - side_length_x) / 2;
+        let side_offset_y = (tile_height - side_length_y) / 2;
+
+        StaggerParams {
+            tile_width: tile_width,
+            tile_height: tile_height,
+            side_length_x: side_length_x,
+            side_length_y: side_length_y,
+            side_offset_x: side_offset_x,
+            side_offset_y: side_offset_y,
+            column_width: side_offset_x + side_length_x,
+            row_height: side_offset_y + side_length_y,
+            stagger_x: stagger_x,
+            stagger_even: stagger_even,
+        }
+    }
+
+    /// Whether the given row or column (depending on the stagger axis) is
+    /// shifted.
+    pub fn is_staggered(&self, index: i32) -> bool {
+        ((index & 1) != 0) != self.stagger_even
+    }
+
+    pub fn tile_to_pixel(&self, x: i32, y: i32) -> (i32, i32) {
+        if self.stagger_x {
+            let mut py = y * (self.tile_height + self.side_length_y);
+            if self.is_staggered(x) {
+                py += self.row_height;
+            }
+            (x * self.column_width, py)
+        } else {
+            let mut px = x * (self.tile_width + self.side_length_x);
+            if self.is_staggered(y) {
+                px += self.column_width;
+            }
+            (px, y * self.row_height)
+        }
+    }
+
+    /// Finds the tile whose center is nearest to the given pixel, according
+    /// to `distance`.
+    pub fn pixel_to_tile<F>(&self, mut px: f32, mut py: f32, distance: F) -> (i32, i32)
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        if self.stagger_x {
+            px -= (if self.stagger_even {
+                self.tile_width
+            } else {
+                self.side_offset_x
+            }) as f32;
+        } else {
+            py -= (if self.stagger_even {
+                self.tile_height
+            } else {
+                self.side_offset_y
+            }) as f32;
+        }
+
+        // the grid-aligned block of tiles the pixel is in
+        let block_width = (self.column_width * 2) as f32;
+        let block_height = (self.row_height * 2) as f32;
+        let mut block_x = (px / block_width).floor() as i32;
+        let mut block_y = (py / block_height).floor() as i32;
+
+        let rel_x = px - block_x as f32 * block_width;
+        let rel_y = py - block_y as f32 * block_height;
+
+        let (centers, offsets) = if self.stagger_x {
+            block_x *= 2;
+            if self.stagger_even {
+                block_x += 1;
+            }
+
+            let left = self.side_length_x / 2;
+            let center_x = left + self.column_width;
+            let center_y = self.tile_height / 2;
+
+            (
+                [
+                    (left, center_y),
+                    (center_x, center_y - self.row_height),
+                    (center_x, center_y + self.row_height),
+                    (center_x + self.column_width, center_y),
+                ],
+                [(0, 0), (1, -1), (1, 0), (2, 0)],
+            )
+        } else {
+            block_y *= 2;
+            if self.stagger_even {
+                block_y += 1;
+            }
+
+            let top = self.side_length_y / 2;
+            let center_x = self.tile_width / 2;
+            let center_y = top + self.row_height;
+
+            (
+                [
+                    (center_x, top),
+                    (center_x - self.column_width, center_y),
+                    (center_x + self.column_width, center_y),
+                    (center_x, center_y + self.row_height),
+                ],
+                [(0, 0), (-1, 1), (0, 1), (0, 2)],
+            )
+        };
+
+        let mut nearest = 0;
+        let mut min_distance = ::std::f32::INFINITY;
+
+        for (i, &(cx, cy)) in centers.iter().enumerate() {
+            let d = distance(cx as f32 - rel_x, cy as f32 - rel_y);
+            if d < min_distance {
+                min_distance = d;
+                nearest = i;
+            }
+        }
+
+        (block_x + offsets[nearest].0, block_y + offsets[nearest].1)
+    }
+}
+
+impl Map {
+    /// Returns the size of the bounding box of all tiles of the map in pixels.
+    pub fn pixel_size(&self) -> (u32, u32) {
+        let (w, h) = (self.width, self.height);
+        let (tw, th) = (self.tilewidth, self.tileheight);
+
+        match self.orientation {
+            Orientation::Orthogonal => (w * tw, h * th),
+            Orientation::Isometric => ((w + h) * tw / 2, (w + h) * th / 2),
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let p = StaggerParams::new(self);
+                let (w, h) = (w as i32, h as i32);
+
+                let (width, height) = if p.stagger_x {
+                    (
+                        w * p.column_width + p.side_offset_x,
+                        h * (p.tile_height + p.side_length_y)
+                            + if w > 1 { p.row_height } else { 0 },
+                    )
+                } else {
+                    (
+                        w * (p.tile_width + p.side_length_x)
+                            + if h > 1 { p.column_width } else { 0 },
+                        h * p.row_height + p.side_offset_y,
+                    )
+                };
+
+                (width as u32, height as u32)
+            }
+        }
+    }
+
+    /// Converts the position of an object to pixels. Objects on isometric maps
+    /// are positioned in a projected space, where both axes are measured in
+    /// units of `tileheight` along the tile axes. For all other orientations,
+    /// this is the identity.
+    pub fn object_to_pixel(&self, x: f32, y: f32) -> (f32, f32) {
+        match self.orientation {
+            Orientation::Isometric => {
+                let (tw, th) = (self.tilewidth as f32, self.tileheight as f32);
+                let (tile_x, tile_y) = (x / th, y / th);
+
+                (
+                    (tile_x - tile_y) * tw / 2.0 + self.height as f32 * tw / 2.0,
+                    (tile_x + tile_y) * th / 2.0,
+                )
+            }
+            _ => (x, y),
+        }
+    }
+
+    /// Returns the pixel position of the top-left corner of the bounding box
+    /// of the tile at `(x, y)`, relative to the top-left corner of the map.
+    pub fn tile_to_pixel(&self, x: i32, y: i32) -> (f32, f32) {
+        let (tw, th) = (self.tilewidth as f32, self.tileheight as f32);
+
+        match self.orientation {
+            Orientation::Orthogonal => (x as f32 * tw, y as f32 * th),
+            Orientation::Isometric => (
+                (x - y) as f32 * tw / 2.0 + (self.height as f32 - 1.0) * tw / 2.0,
+                (x + y) as f32 * th / 2.0,
+            ),
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let (px, py) = StaggerParams::new(self).tile_to_pixel(x, y);
+                (px as f32, py as f32)
+            }
+        }
+    }
+
+    /// Returns the coordinates of the tile that contains the given pixel
+    /// position, relative to the top-left corner of the map. The result can
+    /// lie outside of the map.
+    pub fn pixel_to_tile(&self, px: f32, py: f32) -> (i32, i32) {
+        let (tw, th) = (self.tilewidth as f32, self.tileheight as f32);
+
+        match self.orientation {
+            Orientation::Orthogonal => ((px / tw).floor() as i32, (py / th).floor() as i32),
+            Orientation::Isometric => {
+                // relative to the top corner of the tile at (0, 0)
+                let rel_x = (px - self.height as f32 * tw / 2.0) / tw;
+                let rel_y = py / th;
+
+                (
+                    (rel_y + rel_x).floor() as i32,
+                    (rel_y - rel_x).floor() as i32,
+                )
+            }
+            Orientation::Staggered => {
+                let params = StaggerParams::new(self);
+                let (half_width, half_height) = (
+                    params.tile_width as f32 / 2.0,
+                    params.tile_height as f32 / 2.0,
+                );
+
+                // the tiles are diamonds, so use their manhattan distance
+                params.pixel_to_tile(px, py, |dx, dy| {
+                    dx.abs() / half_width + dy.abs() / half_height
+                })
+            }
+            Orientation::Hexagonal => {
+                StaggerParams::new(self).pixel_to_tile(px, py, |dx, dy| dx * dx + dy * dy)
+            }
+        }
+    }
+}
@@ -146,6 +146,67 @@ impl DataCompression {
     }
 }
 
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+const ALL_FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120;
+
+/// A global tile id as stored in tile layers, including the flags for flipping
+/// and rotating the tile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TileGid(pub u32);
+
+impl TileGid {
+    /// The global tile id without any flags, `0` for empty cells.
+    pub fn gid(&self) -> u32 {
+        self.0 & !ALL_FLAGS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gid() == 0
+    }
+
+    pub fn flipped_horizontally(&self) -> bool {
+        self.0 & FLIPPED_HORIZONTALLY != 0
+    }
+
+    pub fn flipped_vertically(&self) -> bool {
+        self.0 & FLIPPED_VERTICALLY != 0
+    }
+
+    /// Whether the tile is flipped along its top-left to bottom-right
+    /// diagonal. For hexagonal maps, this means a rotation by 60 degrees
+    /// instead.
+    pub fn flipped_diagonally(&self) -> bool {
+        self.0 & FLIPPED_DIAGONALLY != 0
+    }
+
+    /// Only used by hexagonal maps.
+    pub fn rotated_hexagonal_120(&self) -> bool {
+        self.0 & ROTATED_HEXAGONAL_120 != 0
+    }
+
+    /// Maps a position `(u, v)` relative to the drawn tile, with both in
+    /// `0..1`, to the corresponding position in the unflipped tile image.
+    ///
+    /// Tiled applies the diagonal flip first, so it is undone last.
+    pub fn unflip(&self, mut u: f32, mut v: f32) -> (f32, f32) {
+        if self.flipped_vertically() {
+            v = 1.0 - v;
+        }
+        if self.flipped_horizontally() {
+            u = 1.0 - u;
+        }
+        if self.flipped_diagonally() {
+            ::std::mem::swap(&mut u, &mut v);
+        }
+
+        (u, v)
+    }
+}
+
 #[derive(Debug)]
 pub struct Data {
     pub encoding: DataEncoding,
@@ -0,0 +1,322 @@
+use std::f32::consts::PI;
+
+use geometry::ELLIPSE_SEGMENTS;
+use render::{color_factors, Canvas};
+use {Map, Object, Objectgroup};
+
+/// Color of object groups without a `color`, like in tiled.
+const DEFAULT_COLOR: [f32; 4] = [160.0 / 255.0, 160.0 / 255.0, 164.0 / 255.0, 1.0];
+const FILL_ALPHA: f32 = 0.25;
+const POINT_RADIUS: f32 = 3.0;
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+const LABEL_PADDING: u32 = 1;
+
+enum Shape {
+    /// A closed outline, filled when `filled` is set.
+    Polygon {
+        points: Vec<(f32, f32)>,
+        filled: bool,
+    },
+    Polyline(Vec<(f32, f32)>),
+    Point((f32, f32)),
+}
+
+/// Draws the shapes and names of all objects of a group, for debugging.
+/// `offset` and `opacity` are the ones of the group combined with the ones of
+/// the groups it is in.
+pub(crate) fn draw_objects(
+    canvas: &mut Canvas,
+    map: &Map,
+    group: &Objectgroup,
+    offset: (f32, f32),
+    opacity: f32,
+) {
+    let mut color = group.color.as_ref().map_or(DEFAULT_COLOR, color_factors);
+    color[3] *= opacity;
+
+    for object in &group.objects {
+        let shape = object_shape(map, object, offset);
+
+        let (points, anchor) = match shape {
+            Shape::Polygon { ref points, filled } => {
+                if filled {
+                    let mut fill = color;
+                    fill[3] *= FILL_ALPHA;
+                    canvas.fill_polygon(points, fill);
+                }
+                canvas.polyline(points, true, color);
+                (&points[..], None)
+            }
+            Shape::Polyline(ref points) => {
+                canvas.polyline(points, false, color);
+                (&points[..], None)
+            }
+            Shape::Point(p) => {
+                let circle = ellipse_points(
+                    (p.0 - POINT_RADIUS, p.1 - POINT_RADIUS),
+                    (POINT_RADIUS * 2.0, POINT_RADIUS * 2.0),
+                    ELLIPSE_SEGMENTS / 4,
+                );
+                canvas.fill_polygon(&circle, color);
+                (&[][..], Some(p))
+            }
+        };
+
+        let name = match object.name {
+            Some(ref n) if !n.is_empty() => n,
+            _ => continue,
+        };
+
+        // put the label centered above the object
+        let (center_x, top) = match anchor {
+            Some((x, y)) => (x, y - POINT_RADIUS),
+            None => {
+                let min_x = points
+                    .iter()
+                    .map(|p| p.0)
+                    .fold(::std::f32::INFINITY, f32::min);
+                let max_x = points
+                    .iter()
+                    .map(|p| p.0)
+                    .fold(::std::f32::NEG_INFINITY, f32::max);
+                let min_y = points
+                    .iter()
+                    .map(|p| p.1)
+                    .fold(::std::f32::INFINITY, f32::min);
+                ((min_x + max_x) / 2.0, min_y)
+            }
+        };
+
+        if center_x.is_finite() && top.is_finite() {
+            canvas.label(name, center_x, top - 1.0, color);
+        }
+    }
+}
+
+/// Returns the outline of an object in map pixels.
+fn object_shape(map: &Map, object: &Object, offset: (f32, f32)) -> Shape {
+    let points: Vec<(f32, f32)> = object
+        .world_vertices(map, ELLIPSE_SEGMENTS)
+        .into_iter()
+        .map(|(x, y)| (x + offset.0, y + offset.1))
+        .collect();
+
+    // rectangles without a size are drawn like points
+    let empty = object.width.unwrap_or(0.0) == 0.0 && object.height.unwrap_or(0.0) == 0.0;
+    let is_point = object.point.is_some() || (object.is_rectangle() && empty);
+
+    if object.polyline.is_some() {
+        Shape::Polyline(points)
+    } else if is_point {
+        Shape::Point(points[0])
+    } else {
+        Shape::Polygon {
+            points: points,
+            // only the bounds of tile and text objects are drawn
+            filled: object.gid.is_none() && object.text.is_none(),
+        }
+    }
+}
+
+/// Approximates the ellipse inside the given rectangle with a polygon.
+fn ellipse_points(
+    (x, y): (f32, f32),
+    (width, height): (f32, f32),
+    segments: usize,
+) -> Vec<(f32, f32)> {
+    let (rx, ry) = (width / 2.0, height / 2.0);
+
+    (0..segments)
+        .map(|i| {
+            let (sin, cos) = (i as f32 * 2.0 * PI / segments as f32).sin_cos();
+            (x + rx + rx * cos, y + ry + ry * sin)
+        })
+        .collect()
+}
+
+impl Canvas {
+    fn to_canvas(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x - self.origin.0, y - self.origin.1)
+    }
+
+    fn plot(&mut self, x: i64, y: i64, color: [f32; 4]) {
+        if x >= 0 && y >= 0 && x < self.image.width() as i64 && y < self.image.height() as i64 {
+            self.blend(x as u32, y as u32, color);
+        }
+    }
+
+    /// Draws 1 pixel wide lines between the points, given in map pixels.
+    fn polyline(&mut self, points: &[(f32, f32)], closed: bool, color: [f32; 4]) {
+        let segments = if closed {
+            points.len()
+        } else {
+            points.len().saturating_sub(1)
+        };
+
+        for i in 0..segments {
+            let (x0, y0) = self.to_canvas(points[i]);
+            let (x1, y1) = self.to_canvas(points[(i + 1) % points.len()]);
+
+            let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as i64;
+
+            // skip the end point, which is the start of the next segment
+            let last = if closed || i + 1 < segments {
+                steps - 1
+            } else {
+                steps
+            };
+
+            for step in 0..last + 1 {
+                let t = step as f32 / steps as f32;
+                self.plot(
+                    (x0 + (x1 - x0) * t).floor() as i64,
+                    (y0 + (y1 - y0) * t).floor() as i64,
+                    color,
+                );
+            }
+        }
+    }
+
+    /// Fills the polygon with the given corners in map pixels, using the
+    /// even-odd rule.
+    fn fill_polygon(&mut self, points: &[(f32, f32)], color: [f32; 4]) {
+        let points: Vec<_> = points.iter().map(|&p| self.to_canvas(p)).collect();
+        if points.len() < 3 {
+            return;
+        }
+
+        let min_y = points
+            .iter()
+            .map(|p| p.1)
+            .fold(::std::f32::INFINITY, f32::min);
+        let max_y = points
+            .iter()
+            .map(|p| p.1)
+            .fold(::std::f32::NEG_INFINITY, f32::max);
+        let first_row = min_y.floor().max(0.0) as u32;
+        let last_row = max_y.ceil().min(self.image.height() as f32).max(0.0) as u32;
+
+        let mut crossings = Vec::new();
+
+        for row in first_row..last_row {
+            let y = row as f32 + 0.5;
+
+            crossings.clear();
+            for (i, &(x0, y0)) in points.iter().enumerate() {
+                let (x1, y1) = points[(i + 1) % points.len()];
+
+                if (y0 <= y) != (y1 <= y) {
+                    crossings.push(x0 + (y - y0) / (y1 - y0) * (x1 - x0));
+                }
+            }
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal));
+
+            for span in crossings.chunks(2) {
+                if span.len() < 2 {
+                    break;
+                }
+
+                // pixels whose centers are inside the span
+                let first = (span[0] - 0.5).ceil().max(0.0) as i64;
+                let last = (span[1] - 0.5).ceil() as i64;
+                for x in first..last {
+                    self.plot(x, row as i64, color);
+                }
+            }
+        }
+    }
+
+    /// Draws a text label whose bottom center is at the given position in map
+    /// pixels, on a dark background. Letters are drawn in uppercase.
+    fn label(&mut self, text: &str, center_x: f32, bottom: f32, color: [f32; 4]) {
+        let (center_x, bottom) = self.to_canvas((center_x, bottom));
+
+        let chars = text.chars().count() as u32;
+        let width = chars * (GLYPH_WIDTH + 1) - 1 + LABEL_PADDING * 2;
+        let height = GLYPH_HEIGHT + LABEL_PADDING * 2;
+
+        let left = (center_x - width as f32 / 2.0).round() as i64;
+        let top = bottom.round() as i64 - height as i64;
+
+        let background = [0.0, 0.0, 0.0, 0.6 * color[3]];
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                self.plot(left + x, top + y, background);
+            }
+        }
+
+        let foreground = [1.0, 1.0, 1.0, color[3]];
+        let mut x = left + LABEL_PADDING as i64;
+        let y = top + LABEL_PADDING as i64;
+
+        for c in text.chars() {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.plot(x + column as i64, y + row as i64, foreground);
+                    }
+                }
+            }
+
+            x += GLYPH_WIDTH as i64 + 1;
+        }
+    }
+}
+
+/// Returns the rows of a character in a tiny 3x5 pixel font.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '?' => [0b111, 0b001, 0b010, 0b000, 0b010],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        // anything else is drawn as a filled box
+        _ => [0b111, 0b111, 0b111, 0b111, 0b111],
+    }
+}
@@ -0,0 +1,286 @@
+use std::f32::consts::PI;
+
+use {Map, Object, Orientation, TileGid};
+
+pub(crate) type Point = (f32, f32);
+
+/// Number of points ellipses are approximated with, where they can not be
+/// handled exactly.
+pub(crate) const ELLIPSE_SEGMENTS: usize = 32;
+
+/// Maps points relative to an object to map pixels: `origin + matrix * p`.
+struct Transform {
+    origin: Point,
+    /// Row-major 2x2 matrix.
+    matrix: [[f32; 2]; 2],
+}
+
+impl Transform {
+    fn apply(&self, (x, y): Point) -> Point {
+        let m = &self.matrix;
+        (
+            self.origin.0 + m[0][0] * x + m[0][1] * y,
+            self.origin.1 + m[1][0] * x + m[1][1] * y,
+        )
+    }
+
+    /// Maps a point in map pixels back to a point relative to the object.
+    fn invert(&self, (x, y): Point) -> Option<Point> {
+        let m = &self.matrix;
+        let determinant = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+        if determinant == 0.0 {
+            return None;
+        }
+
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+        Some((
+            (m[1][1] * dx - m[0][1] * dy) / determinant,
+            (m[0][0] * dy - m[1][0] * dx) / determinant,
+        ))
+    }
+}
+
+impl Object {
+    /// Whether this is a plain rectangle, rather than a tile, ellipse, point,
+    /// polyline, polygon or text object.
+    pub(crate) fn is_rectangle(&self) -> bool {
+        self.gid.is_none()
+            && self.ellipse.is_none()
+            && self.point.is_none()
+            && self.polyline.is_none()
+            && self.polygon.is_none()
+            && self.text.is_none()
+    }
+
+    /// Whether the vertices of the object enclose an area. Points and
+    /// polylines do not.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.point.is_none() && self.polyline.is_none()
+    }
+
+    /// The size of the object. Tile objects without a size have the size of
+    /// their tile.
+    fn size(&self, map: &Map) -> (f32, f32) {
+        let tile_size = self
+            .gid
+            .and_then(|gid| map.tileset_for_gid(TileGid(gid)))
+            .and_then(|(tileset, local_id)| tileset.tile_rect(local_id))
+            .map_or((0.0, 0.0), |(_, _, w, h)| (w as f32, h as f32));
+
+        (
+            self.width.unwrap_or(tile_size.0),
+            self.height.unwrap_or(tile_size.1),
+        )
+    }
+
+    /// The transform from points relative to the object to map pixels.
+    ///
+    /// Shapes are projected on isometric maps (see `Map::object_to_pixel`),
+    /// and then rotated around the position of the object. Tile objects are
+    /// not projected, but offset like their tileset.
+    fn transform(&self, map: &Map) -> Transform {
+        let origin = map.object_to_pixel(self.x, self.y);
+        let (sin, cos) = self.rotation.unwrap_or(0.0).to_radians().sin_cos();
+
+        if let Some(gid) = self.gid {
+            let offset = map
+                .tileset_for_gid(TileGid(gid))
+                .map_or((0.0, 0.0), |(t, _)| {
+                    (t.tileoffset.x as f32, t.tileoffset.y as f32)
+                });
+
+            return Transform {
+                origin: (origin.0 + offset.0, origin.1 + offset.1),
+                matrix: [[cos, -sin], [sin, cos]],
+            };
+        }
+
+        // object_to_pixel is affine, so its linear part follows from the
+        // images of the unit vectors
+        let unit_x = map.object_to_pixel(self.x + 1.0, self.y);
+        let unit_y = map.object_to_pixel(self.x, self.y + 1.0);
+        let projection = [
+            [unit_x.0 - origin.0, unit_y.0 - origin.0],
+            [unit_x.1 - origin.1, unit_y.1 - origin.1],
+        ];
+
+        let rotation = [[cos, -sin], [sin, cos]];
+        let mut matrix = [[0.0; 2]; 2];
+        for row in 0..2 {
+            for column in 0..2 {
+                matrix[row][column] = rotation[row][0] * projection[0][column]
+                    + rotation[row][1] * projection[1][column];
+            }
+        }
+
+        Transform {
+            origin: origin,
+            matrix: matrix,
+        }
+    }
+
+    /// The points of the shape relative to the object, before it is
+    /// transformed. Tile objects are anchored at `(anchor_x, 0)`.
+    fn local_points(
+        &self,
+        (width, height): (f32, f32),
+        anchor_x: f32,
+        ellipse_segments: usize,
+    ) -> Vec<Point> {
+        if self.gid.is_some() {
+            let (left, right) = (anchor_x, anchor_x + width);
+            vec![(left, -height), (right, -height), (right, 0.0), (left, 0.0)]
+        } else if let Some(ref polygon) = self.polygon {
+            polygon.points.clone()
+        } else if let Some(ref polyline) = self.polyline {
+            polyline.points.clone()
+        } else if self.point.is_some() {
+            vec![(0.0, 0.0)]
+        } else if self.ellipse.is_some() {
+            let (rx, ry) = (width / 2.0, height / 2.0);
+
+            (0..ellipse_segments)
+                .map(|i| {
+                    let (sin, cos) = (i as f32 * 2.0 * PI / ellipse_segments as f32).sin_cos();
+                    (rx + rx * cos, ry + ry * sin)
+                })
+                .collect()
+        } else {
+            vec![(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)]
+        }
+    }
+
+    /// Returns the vertices of the object with its rotation applied, in the
+    /// same space as `x` and `y`, without projecting them like
+    /// `world_vertices`. Tile objects are anchored at their bottom-left
+    /// corner, and have the size of their tile if they have none.
+    pub(crate) fn vertices(&self, map: &Map, ellipse_segments: usize) -> Vec<Point> {
+        let size = self.size(map);
+        let (sin, cos) = self.rotation.unwrap_or(0.0).to_radians().sin_cos();
+
+        self.local_points(size, 0.0, ellipse_segments)
+            .into_iter()
+            .map(|(x, y)| (self.x + x * cos - y * sin, self.y + x * sin + y * cos))
+            .collect()
+    }
+
+    /// Returns the vertices of the object in map pixels, with its rotation
+    /// and the projection of isometric maps applied: the corners of
+    /// rectangles, tile and text objects, the points of polygons and
+    /// polylines, and the position of point objects. Ellipses are
+    /// approximated with `ellipse_segments` points.
+    ///
+    /// Tile objects are anchored at their bottom-left corner, or at their
+    /// bottom center on isometric maps. The offset of the object group is not
+    /// included.
+    pub fn world_vertices(&self, map: &Map, ellipse_segments: usize) -> Vec<Point> {
+        let size = self.size(map);
+        // tile objects are anchored at their bottom-left corner, or at their
+        // bottom center on isometric maps
+        let anchor_x = match map.orientation {
+            Orientation::Isometric => -size.0 / 2.0,
+            _ => 0.0,
+        };
+        let transform = self.transform(map);
+
+        self.local_points(size, anchor_x, ellipse_segments)
+            .into_iter()
+            .map(|p| transform.apply(p))
+            .collect()
+    }
+
+    /// Returns the axis-aligned bounding box `(x, y, width, height)` of the
+    /// object in map pixels, see `world_vertices`. Ellipses are bounded
+    /// exactly.
+    pub fn world_bounds(&self, map: &Map) -> (f32, f32, f32, f32) {
+        if self.ellipse.is_some() && self.gid.is_none() {
+            let (width, height) = self.size(map);
+            let (rx, ry) = (width / 2.0, height / 2.0);
+            let transform = self.transform(map);
+            let m = &transform.matrix;
+
+            let (cx, cy) = transform.apply((rx, ry));
+            let extent_x = (m[0][0] * rx).hypot(m[0][1] * ry);
+            let extent_y = (m[1][0] * rx).hypot(m[1][1] * ry);
+
+            return (cx - extent_x, cy - extent_y, extent_x * 2.0, extent_y * 2.0);
+        }
+
+        let vertices = self.world_vertices(map, 0);
+        if vertices.is_empty() {
+            let (x, y) = self.transform(map).origin;
+            return (x, y, 0.0, 0.0);
+        }
+
+        let (min_x, min_y, max_x, max_y) = vertices.iter().fold(
+            (
+                ::std::f32::INFINITY,
+                ::std::f32::INFINITY,
+                ::std::f32::NEG_INFINITY,
+                ::std::f32::NEG_INFINITY,
+            ),
+            |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+        );
+
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+
+    /// Whether the point, in map pixels, is inside of the object (see
+    /// `world_vertices`). Ellipses are tested exactly. Points and polylines
+    /// have no area, so they never contain a point.
+    pub fn contains_point(&self, map: &Map, point: Point) -> bool {
+        if !self.is_closed() {
+            return false;
+        }
+
+        if self.ellipse.is_some() && self.gid.is_none() {
+            let (width, height) = self.size(map);
+            let (rx, ry) = (width / 2.0, height / 2.0);
+
+            return match self.transform(map).invert(point) {
+                Some((x, y)) if rx > 0.0 && ry > 0.0 => {
+                    let (dx, dy) = ((x - rx) / rx, (y - ry) / ry);
+                    dx * dx + dy * dy <= 1.0
+                }
+                _ => false,
+            };
+        }
+
+        polygon_contains(&self.world_vertices(map, 0), point)
+    }
+}
+
+/// Whether the point is inside of the polygon, by the even-odd rule.
+pub(crate) fn polygon_contains(polygon: &[Point], (x, y): Point) -> bool {
+    let mut inside = false;
+
+    for i in 0..polygon.len() {
+        let (x0, y0) = polygon[i];
+        let (x1, y1) = polygon[(i + 1) % polygon.len()];
+
+        if (y0 > y) != (y1 > y) && x < x0 + (y - y0) / (y1 - y0) * (x1 - x0) {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+pub(crate) fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+/// The cross product of two vectors.
+pub(crate) fn cross(a: Point, b: Point) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+/// Positive if `c` is to the right of the line from `a` to `b`, as seen on
+/// screen with the y axis pointing down.
+pub(crate) fn turn(a: Point, b: Point, c: Point) -> f32 {
+    cross(sub(b, a), sub(c, a))
+}
+
+pub(crate) fn distance(a: Point, b: Point) -> f32 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
@@ -0,0 +1,180 @@
+use {Map, Orientation, StaggerAxis, StaggerIndex};
+
+/// Neighbour, distance, line and range queries for hexagonal maps, working
+/// directly on the offset coordinates of tiles.
+///
+/// Results can lie outside of the map, so they might have to be filtered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexGrid {
+    pub stagger_axis: StaggerAxis,
+    pub stagger_index: StaggerIndex,
+}
+
+// Axial directions, counter-clockwise starting east (stagger axis y) or
+// south-east (stagger axis x).
+const DIRECTIONS: [(i32, i32); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Axial {
+    q: i32,
+    r: i32,
+}
+
+impl Axial {
+    fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    fn offset(&self, (dq, dr): (i32, i32), times: i32) -> Axial {
+        Axial {
+            q: self.q + dq * times,
+            r: self.r + dr * times,
+        }
+    }
+}
+
+impl Map {
+    /// Returns the hex grid of this map, or `None` if it is not hexagonal.
+    pub fn hex_grid(&self) -> Option<HexGrid> {
+        if self.orientation != Orientation::Hexagonal {
+            return None;
+        }
+
+        Some(HexGrid {
+            stagger_axis: self.staggeraxis.unwrap_or(StaggerAxis::Y),
+            stagger_index: self.staggerindex.unwrap_or(StaggerIndex::Odd),
+        })
+    }
+}
+
+impl HexGrid {
+    fn shift(&self, index: i32) -> i32 {
+        match self.stagger_index {
+            StaggerIndex::Odd => (index - (index & 1)) / 2,
+            StaggerIndex::Even => (index + (index & 1)) / 2,
+        }
+    }
+
+    fn to_axial(&self, (x, y): (i32, i32)) -> Axial {
+        match self.stagger_axis {
+            StaggerAxis::X => Axial {
+                q: x,
+                r: y - self.shift(x),
+            },
+            StaggerAxis::Y => Axial {
+                q: x - self.shift(y),
+                r: y,
+            },
+        }
+    }
+
+    fn from_axial(&self, a: Axial) -> (i32, i32) {
+        match self.stagger_axis {
+            StaggerAxis::X => (a.q, a.r + self.shift(a.q)),
+            StaggerAxis::Y => (a.q + self.shift(a.r), a.r),
+        }
+    }
+
+    /// Returns the 6 neighbours of a tile, in counter-clockwise order starting
+    /// with the one to the east (stagger axis y) or south-east (stagger
+    /// axis x).
+    pub fn neighbors(&self, tile: (i32, i32)) -> [(i32, i32); 6] {
+        let center = self.to_axial(tile);
+        let mut neighbors = [(0, 0); 6];
+
+        for (neighbor, &dir) in neighbors.iter_mut().zip(DIRECTIONS.iter()) {
+            *neighbor = self.from_axial(center.offset(dir, 1));
+        }
+
+        neighbors
+    }
+
+    /// Returns the number of steps between two tiles.
+    pub fn distance(&self, a: (i32, i32), b: (i32, i32)) -> u32 {
+        let (a, b) = (self.to_axial(a), self.to_axial(b));
+
+        (((a.q - b.q).abs() + (a.r - b.r).abs() + (a.s() - b.s()).abs()) / 2) as u32
+    }
+
+    /// Returns the tiles on the straight line from `a` to `b`, including both.
+    pub fn line(&self, a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+        let steps = self.distance(a, b);
+        let (a, b) = (self.to_axial(a), self.to_axial(b));
+
+        // nudge the line slightly, so it does not run exactly along edges
+        let (aq, ar) = (a.q as f32 + 1e-6, a.r as f32 + 1e-6);
+        let (bq, br) = (b.q as f32 + 1e-6, b.r as f32 + 1e-6);
+
+        (0..steps + 1)
+            .map(|i| {
+                let t = if steps == 0 {
+                    0.0
+                } else {
+                    i as f32 / steps as f32
+                };
+
+                self.from_axial(round_axial(aq + (bq - aq) * t, ar + (br - ar) * t))
+            })
+            .collect()
+    }
+
+    /// Returns all tiles at most `radius` steps away from `center`, including
+    /// `center` itself.
+    pub fn range(&self, center: (i32, i32), radius: u32) -> Vec<(i32, i32)> {
+        let center = self.to_axial(center);
+        let n = radius as i32;
+        let mut tiles = Vec::new();
+
+        for dq in -n..n + 1 {
+            let first = ::std::cmp::max(-n, -dq - n);
+            let last = ::std::cmp::min(n, -dq + n);
+
+            for dr in first..last + 1 {
+                tiles.push(self.from_axial(center.offset((dq, dr), 1)));
+            }
+        }
+
+        tiles
+    }
+
+    /// Returns all tiles exactly `radius` steps away from `center`, going
+    /// around it counter-clockwise.
+    pub fn ring(&self, center: (i32, i32), radius: u32) -> Vec<(i32, i32)> {
+        let center = self.to_axial(center);
+        let n = radius as i32;
+
+        if n == 0 {
+            return vec![self.from_axial(center)];
+        }
+
+        let mut tiles = Vec::with_capacity(6 * radius as usize);
+        let mut current = center.offset(DIRECTIONS[4], n);
+
+        for &dir in DIRECTIONS.iter() {
+            for _ in 0..n {
+                tiles.push(self.from_axial(current));
+                current = current.offset(dir, 1);
+            }
+        }
+
+        tiles
+    }
+}
+
+fn round_axial(q: f32, r: f32) -> Axial {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+
+    Axial {
+        q: rq as i32,
+        r: rr as i32,
+    }
+}
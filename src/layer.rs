@@ -0,0 +1,480 @@
+use std::fmt;
+use std::slice;
+
+use serde::de;
+
+use coords::StaggerParams;
+use map::PropertiesElement;
+use warnings;
+use {
+    Color, Data, Image, LoadWarning, Map, MapLayer, Object, Objectgroup, Orientation, Properties,
+    StaggerAxis, TileGid, TileRenderOrder,
+};
+
+/// Attributes that are shared by all kinds of layers.
+#[derive(Debug)]
+pub struct LayerCommon {
+    /// Unique id of the layer, `0` for maps saved before tiled 1.2.
+    pub id: u32,
+    pub name: String,
+    pub class: Option<String>,
+
+    pub visible: bool,
+    pub locked: bool,
+    pub opacity: f32,
+    pub tintcolor: Option<Color>,
+
+    pub offsetx: f32,
+    pub offsety: f32,
+    pub parallaxx: f32,
+    pub parallaxy: f32,
+
+    pub properties: Option<Properties>,
+}
+
+#[derive(Debug)]
+pub struct Layer {
+    pub common: LayerCommon,
+
+    pub width: u32,
+    pub height: u32,
+
+    pub data: Data,
+}
+
+impl Layer {
+    /// Returns the tile at `(x, y)`, or `None` if that is outside of the layer.
+    pub fn tile_gid(&self, x: u32, y: u32) -> Option<TileGid> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.data
+            .tile_gids
+            .get((y * self.width + x) as usize)
+            .map(|&gid| TileGid(gid))
+    }
+
+    /// Iterates over all non-empty tiles `(x, y, gid)` in the order they have
+    /// to be drawn in, so that overlapping tiles are drawn correctly.
+    ///
+    /// Tiles are drawn row by row, where rows of isometric maps run
+    /// diagonally, and rows of maps staggered along the x axis consist of the
+    /// upper columns, followed by the lower ones. The map's `renderorder` is
+    /// only applied to orthogonal maps, like tiled does: tiles of other
+    /// orientations always overlap as if it was `right-down`.
+    pub fn tiles_in_render_order<'a>(
+        &'a self,
+        map: &Map,
+    ) -> impl Iterator<Item = (u32, u32, TileGid)> + 'a {
+        render_order(map, self.width, self.height)
+            .into_iter()
+            .filter_map(move |(x, y)| {
+                self.tile_gid(x, y)
+                    .filter(|gid| !gid.is_empty())
+                    .map(|gid| (x, y, gid))
+            })
+    }
+}
+
+// Returns all positions of a layer in render order.
+fn render_order(map: &Map, width: u32, height: u32) -> Vec<(u32, u32)> {
+    // screen rows from top to bottom, each from left to right
+    let mut rows: Vec<Vec<(u32, u32)>> = Vec::new();
+
+    match map.orientation {
+        Orientation::Isometric => {
+            let (width, height) = (width as i64, height as i64);
+
+            for diagonal in 0..(width + height - 1) {
+                let first = ::std::cmp::max(0, diagonal - (height - 1));
+                let last = ::std::cmp::min(diagonal, width - 1);
+
+                rows.push(
+                    (first..last + 1)
+                        .map(|x| (x as u32, (diagonal - x) as u32))
+                        .collect(),
+                );
+            }
+        }
+        Orientation::Staggered | Orientation::Hexagonal
+            if map.staggeraxis == Some(StaggerAxis::X) =>
+        {
+            let params = StaggerParams::new(map);
+
+            for y in 0..height {
+                for &lower in &[false, true] {
+                    rows.push(
+                        (0..width)
+                            .filter(|&x| params.is_staggered(x as i32) == lower)
+                            .map(|x| (x, y))
+                            .collect(),
+                    );
+                }
+            }
+        }
+        _ => {
+            for y in 0..height {
+                rows.push((0..width).map(|x| (x, y)).collect());
+            }
+        }
+    }
+
+    let (right, down) = match map.renderorder {
+        _ if map.orientation != Orientation::Orthogonal => (true, true),
+        TileRenderOrder::RightDown => (true, true),
+        TileRenderOrder::RightUp => (true, false),
+        TileRenderOrder::LeftDown => (false, true),
+        TileRenderOrder::LeftUp => (false, false),
+    };
+
+    if !down {
+        rows.reverse();
+    }
+
+    rows.into_iter()
+        .flat_map(|mut row| {
+            if !right {
+                row.reverse();
+            }
+            row
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct ImageLayer {
+    pub common: LayerCommon,
+
+    pub image: Image,
+}
+
+/// A layer that contains other layers, including further groups.
+#[derive(Debug)]
+pub struct GroupLayer {
+    pub common: LayerCommon,
+
+    pub layers: Vec<MapLayer>,
+}
+
+/// Iterates over the layers of a map and the layers nested in its groups,
+/// see `Map::all_layers`.
+pub struct AllLayers<'a> {
+    stack: Vec<slice::Iter<'a, MapLayer>>,
+}
+
+impl<'a> Iterator for AllLayers<'a> {
+    type Item = &'a MapLayer;
+
+    fn next(&mut self) -> Option<&'a MapLayer> {
+        loop {
+            let layer = match self.stack.last_mut()?.next() {
+                Some(layer) => layer,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            if let MapLayer::Group(ref group) = *layer {
+                self.stack.push(group.layers.iter());
+            }
+
+            return Some(layer);
+        }
+    }
+}
+
+impl Map {
+    /// Iterates over all layers of the map, with the layers of each group
+    /// following the group itself.
+    pub fn all_layers(&self) -> AllLayers {
+        AllLayers {
+            stack: vec![self.layers.iter()],
+        }
+    }
+
+    /// Iterates over all object groups of the map, including the ones nested
+    /// in groups.
+    pub fn object_groups(&self) -> impl Iterator<Item = &Objectgroup> {
+        self.all_layers().filter_map(|l| match *l {
+            MapLayer::ObjectGroup(ref o) => Some(o),
+            _ => None,
+        })
+    }
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// The attributes and children of all kinds of layers, which are then split
+// up by the `Deserialize` impl of each layer.
+#[derive(Debug, Deserialize)]
+struct LayerImpl {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    class: Option<String>,
+
+    #[serde(
+        deserialize_with = "::util::deserialize_bool",
+        default = "default_true"
+    )]
+    visible: bool,
+    #[serde(deserialize_with = "::util::deserialize_bool", default)]
+    locked: bool,
+    #[serde(default = "default_one")]
+    opacity: f32,
+    tintcolor: Option<Color>,
+
+    #[serde(default)]
+    offsetx: f32,
+    #[serde(default)]
+    offsety: f32,
+    #[serde(default = "default_one")]
+    parallaxx: f32,
+    #[serde(default = "default_one")]
+    parallaxy: f32,
+
+    // deprecated, always 0
+    x: Option<String>,
+    y: Option<String>,
+
+    // tile layers
+    width: Option<u32>,
+    height: Option<u32>,
+
+    // object groups
+    draworder: Option<String>,
+    color: Option<Color>,
+
+    #[serde(rename = "$value", default)]
+    children: Vec<LayerChild>,
+}
+
+/// A child element of a layer of any kind. Only groups contain other layers.
+#[derive(Debug)]
+enum LayerChild {
+    Properties(Option<Properties>),
+    Data(Data),
+    Object(Object),
+    Image(Image),
+    Layer(MapLayer),
+    Tileset,
+    Skipped,
+}
+
+impl<'de> de::Deserialize<'de> for LayerChild {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<LayerChild, D::Error> {
+        const ELEMENTS: &[&str] = &[
+            "properties",
+            "data",
+            "object",
+            "image",
+            "layer",
+            "objectgroup",
+            "imagelayer",
+            "group",
+        ];
+
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = LayerChild;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("properties, data, an object, an image or a layer")
+            }
+
+            fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<LayerChild, A::Error> {
+                use serde::de::VariantAccess;
+
+                let (name, variant): (String, _) = data.variant()?;
+
+                match &name[..] {
+                    "properties" => variant
+                        .newtype_variant()
+                        .map(|p: PropertiesElement| LayerChild::Properties(p.0)),
+                    "data" => variant.newtype_variant().map(LayerChild::Data),
+                    "object" => variant.newtype_variant().map(LayerChild::Object),
+                    "image" => variant.newtype_variant().map(LayerChild::Image),
+                    "layer" => variant
+                        .newtype_variant()
+                        .map(|l| LayerChild::Layer(MapLayer::Layer(l))),
+                    "objectgroup" => variant
+                        .newtype_variant()
+                        .map(|o| LayerChild::Layer(MapLayer::ObjectGroup(o))),
+                    "imagelayer" => variant
+                        .newtype_variant()
+                        .map(|il| LayerChild::Layer(MapLayer::ImageLayer(il))),
+                    "group" => variant
+                        .newtype_variant()
+                        .map(|g| LayerChild::Layer(MapLayer::Group(g))),
+                    "tileset" => variant
+                        .newtype_variant::<de::IgnoredAny>()
+                        .map(|_| LayerChild::Tileset),
+                    _ => {
+                        variant.newtype_variant::<de::IgnoredAny>()?;
+                        warnings::warn(LoadWarning::UnknownElement { name: name });
+                        Ok(LayerChild::Skipped)
+                    }
+                }
+            }
+        }
+
+        deserializer.deserialize_enum("LayerChild", ELEMENTS, Visitor)
+    }
+}
+
+impl LayerImpl {
+    fn split<E: de::Error>(self, element: &str) -> Result<(LayerCommon, LayerContent), E> {
+        for &(attribute, value) in &[("x", &self.x), ("y", &self.y)] {
+            if value.is_some() {
+                warnings::warn(LoadWarning::DeprecatedAttribute {
+                    element: element.into(),
+                    attribute: attribute.into(),
+                });
+            }
+        }
+
+        let mut properties = None;
+        let mut content = LayerContent {
+            width: self.width,
+            height: self.height,
+            data: None,
+
+            draworder: self.draworder,
+            color: self.color,
+            objects: Vec::new(),
+
+            image: None,
+
+            layers: Vec::new(),
+        };
+
+        for child in self.children {
+            match child {
+                LayerChild::Properties(p) => {
+                    if properties.is_none() {
+                        properties = p;
+                    } else {
+                        return Err(E::custom("multiple properties encountered"));
+                    }
+                }
+                LayerChild::Data(d) => content.data = Some(d),
+                LayerChild::Object(o) => content.objects.push(o),
+                LayerChild::Image(i) => content.image = Some(i),
+                LayerChild::Layer(l) => content.layers.push(l),
+                LayerChild::Tileset => {
+                    return Err(E::custom(format!(
+                        "{} '{}' contains a tileset",
+                        element, self.name
+                    )))
+                }
+                LayerChild::Skipped => {}
+            }
+        }
+
+        let common = LayerCommon {
+            id: self.id,
+            name: self.name,
+            class: self.class,
+
+            visible: self.visible,
+            locked: self.locked,
+            opacity: self.opacity,
+            tintcolor: self.tintcolor,
+
+            offsetx: self.offsetx,
+            offsety: self.offsety,
+            parallaxx: self.parallaxx,
+            parallaxy: self.parallaxy,
+
+            properties: properties,
+        };
+
+        Ok((common, content))
+    }
+}
+
+struct LayerContent {
+    width: Option<u32>,
+    height: Option<u32>,
+    data: Option<Data>,
+
+    draworder: Option<String>,
+    color: Option<Color>,
+    objects: Vec<Object>,
+
+    image: Option<Image>,
+
+    // groups
+    layers: Vec<MapLayer>,
+}
+
+fn missing<E: de::Error>(layer: &LayerCommon, what: &str) -> E {
+    de::Error::custom(format!("layer '{}' is missing {}", layer.name, what))
+}
+
+impl<'de> de::Deserialize<'de> for Layer {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Layer, D::Error> {
+        let li: LayerImpl = de::Deserialize::deserialize(deserializer)?;
+        let (common, content) = li.split("layer")?;
+
+        Ok(Layer {
+            width: content.width.ok_or_else(|| missing(&common, "width"))?,
+            height: content.height.ok_or_else(|| missing(&common, "height"))?,
+            data: content.data.ok_or_else(|| missing(&common, "data"))?,
+
+            common: common,
+        })
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Objectgroup {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Objectgroup, D::Error> {
+        let li: LayerImpl = de::Deserialize::deserialize(deserializer)?;
+        let (common, content) = li.split("objectgroup")?;
+
+        Ok(Objectgroup {
+            common: common,
+
+            draworder: content.draworder,
+            color: content.color,
+
+            objects: content.objects,
+        })
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ImageLayer {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<ImageLayer, D::Error> {
+        let li: LayerImpl = de::Deserialize::deserialize(deserializer)?;
+        let (common, content) = li.split("imagelayer")?;
+
+        Ok(ImageLayer {
+            image: content.image.ok_or_else(|| missing(&common, "image"))?,
+
+            common: common,
+        })
+    }
+}
+
+impl<'de> de::Deserialize<'de> for GroupLayer {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<GroupLayer, D::Error> {
+        let li: LayerImpl = de::Deserialize::deserialize(deserializer)?;
+        let (common, content) = li.split("group")?;
+
+        Ok(GroupLayer {
+            common: common,
+
+            layers: content.layers,
+        })
+    }
+}
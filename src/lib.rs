@@ -1,3 +1,4 @@
+#[macro_use]
 extern crate serde;
 extern crate serde_xml_rs;
 
@@ -8,38 +9,144 @@ extern crate base64;
 extern crate byteorder;
 extern crate flate2;
 extern crate regex;
+extern crate serde_json;
 
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(feature = "render")]
+extern crate image;
+
 #[macro_use]
 mod util;
 
 mod data;
 mod properties;
+mod property_de;
+mod project;
+mod references;
 mod objects;
+mod layer;
 mod map;
 mod color;
-
-pub use data::{Data, DataCompression, DataEncoding};
-pub use properties::Properties;
-pub use objects::{Ellipse, Object, Objectgroup, Polygon, Polyline};
+mod tileset;
+mod wang;
+mod autotile;
+mod collision;
+mod contour;
+mod coords;
+mod geometry;
+mod hex;
+mod path;
+mod navmesh;
+mod search;
+mod spatial;
+mod template;
+mod mesh;
+mod version;
+mod warnings;
+#[cfg(feature = "render")]
+mod render;
+#[cfg(feature = "render")]
+mod debug_draw;
+#[cfg(feature = "render")]
+mod atlas;
+
+pub use collision::CollisionGrid;
+pub use contour::Contour;
+pub use data::{Data, DataCompression, DataEncoding, TileGid};
+pub use hex::HexGrid;
+pub use path::{Connectivity, NavGrid};
+pub use navmesh::{NavMesh, NavPolygon, Portal};
+pub use spatial::{RayHit, SpatialIndex};
+pub use template::{Template, TemplateTileset};
+pub use mesh::{TileBatch, VERTEX_SIZE};
+pub use properties::{FromProperty, Properties, PropertiesIter, PropertyError};
+pub use project::{ClassType, EnumStorage, EnumType, JsonError, Project, PropertyType};
+pub use references::ValidationError;
+#[cfg(feature = "render")]
+pub use render::{RenderError, RenderOptions, Renderer};
+pub use layer::{AllLayers, GroupLayer, ImageLayer, Layer, LayerCommon};
+pub use objects::{Ellipse, Object, Objectgroup, Polygon, Polyline, Text};
+pub use tileset::{Grid, GridOrientation, Tile, TileOffset, Tileset};
+pub use autotile::{autotile, AutotileError};
+#[cfg(feature = "render")]
+pub use atlas::{pack_atlas, Atlas, AtlasError, AtlasOptions, AtlasRect};
+pub use version::{LoadOptions, Version, VersionPolicy};
+pub use warnings::LoadWarning;
+pub use wang::{Terrain, TileTerrain, WangColor, WangId, WangSet, WangSetType, WangTile};
 
 pub type XmlError = serde_xml_rs::Error;
 
 pub fn load_from_str(map_str: &str) -> Result<Map, XmlError> {
-    serde_xml_rs::from_str(map_str)
+    load_from_str_with(map_str, &LoadOptions::default())
+}
+
+pub fn load_from_str_with(map_str: &str, options: &LoadOptions) -> Result<Map, XmlError> {
+    let (map, warnings) = warnings::collect(|| serde_xml_rs::from_str::<Map>(map_str));
+    let mut map = map?;
+    map.warnings = warnings;
+
+    // `version` is the version of the file format, which can not be compared
+    // to versions of tiled
+    match map.tiledversion {
+        Some(version) if version < options.minimum_version => match options.old_versions {
+            VersionPolicy::Refuse => {
+                use serde::de::Error;
+
+                return Err(XmlError::custom(format!(
+                    "map was saved by tiled {}, but only {} and newer are supported",
+                    version, options.minimum_version
+                )));
+            }
+            VersionPolicy::Warn => map.warnings.push(LoadWarning::OldVersion {
+                version: version,
+                minimum: options.minimum_version,
+            }),
+        },
+        Some(_) => {}
+        None if !options.require_tiledversion => {}
+        None => match options.old_versions {
+            VersionPolicy::Refuse => {
+                use serde::de::Error;
+
+                return Err(XmlError::custom(
+                    "map does not say which version of tiled saved it",
+                ));
+            }
+            VersionPolicy::Warn => map.warnings.push(LoadWarning::MissingTiledVersion),
+        },
+    }
+
+    if let Some(ref project) = options.project {
+        map.apply_project(project);
+        map.project = Some(project.clone());
+    }
+
+    Ok(map)
 }
 
 pub fn load_from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Map, XmlError> {
+    load_from_path_with(path, &LoadOptions::default())
+}
+
+pub fn load_from_path_with<P: AsRef<std::path::Path>>(
+    path: P,
+    options: &LoadOptions,
+) -> Result<Map, XmlError> {
     use std::fs::File;
     use std::io::Read;
 
-    let mut file = File::open(path)?;
+    let mut file = File::open(path.as_ref())?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
 
-    load_from_str(&content)
+    let mut map = load_from_str_with(&content, options)?;
+    if let Some(dir) = path.as_ref().parent() {
+        map.load_templates(dir);
+    }
+
+    Ok(map)
 }
 
 #[derive(Debug, Deserialize)]
@@ -51,36 +158,23 @@ pub struct Image {
     pub source: String,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Property {
     String(String),
     Bool(bool),
     Float(f64),
     Int(i64),
-}
-
-#[derive(Debug, Deserialize)]
-pub struct Tile {
-    pub id: u32,
-
-    #[serde(deserialize_with = "::properties::deserialize_properties")]
-    #[serde(default)]
-    pub properties: Option<Properties>,
-    pub image: Option<Image>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct Tileset {
-    pub firstgid: u32,
-    pub name: String,
-    pub tilewidth: u32,
-    pub tileheight: u32,
-    pub tilecount: u32,
-    pub columns: u32,
-
-    #[serde(rename(deserialize = "tile"), default)]
-    pub tiles: Vec<Tile>,
-    pub image: Option<Image>,
+    Color(Color),
+    /// The id of an object in the map, or `0` if no object is set. See
+    /// `Map::resolve_object_property`.
+    Object(u32),
+    /// A property with members, whose names and types are defined by the
+    /// custom type `propertytype`. Members that were not changed from their
+    /// defaults are not stored.
+    Class {
+        propertytype: String,
+        properties: Properties,
+    },
 }
 
 enum_str!(Orientation {
@@ -107,36 +201,24 @@ enum_str!(TileRenderOrder {
     LeftUp("left-up"),
 });
 
-#[derive(Debug, Deserialize)]
-pub struct Layer {
-    pub name: String,
-    pub width: u32,
-    pub height: u32,
-    pub opacity: Option<f32>,
-
-    pub data: Data,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct ImageLayer {
-    pub name: String,
-    pub opacity: Option<f32>,
-    pub offsetx: Option<f32>,
-    pub offsety: Option<f32>,
-    pub visible: Option<u8>,
-
-    pub image: Image,
-
-    #[serde(deserialize_with = "::properties::deserialize_properties")]
-    #[serde(default)]
-    pub properties: Option<Properties>,
-}
-
 #[derive(Debug)]
 pub enum MapLayer {
     Layer(Layer),
     ObjectGroup(Objectgroup),
     ImageLayer(ImageLayer),
+    Group(GroupLayer),
+}
+
+impl MapLayer {
+    /// Returns the attributes shared by all kinds of layers.
+    pub fn common(&self) -> &LayerCommon {
+        match *self {
+            MapLayer::Layer(ref l) => &l.common,
+            MapLayer::ObjectGroup(ref o) => &o.common,
+            MapLayer::ImageLayer(ref il) => &il.common,
+            MapLayer::Group(ref g) => &g.common,
+        }
+    }
 }
 
 /// A `Map` is the main type returned when loading a tiled file. It contains the
@@ -144,7 +226,11 @@ pub enum MapLayer {
 /// correct order (as specified in tiled.)
 #[derive(Debug)]
 pub struct Map {
-    pub version: String,
+    /// Version of the map format.
+    pub version: Version,
+    /// Version of tiled that saved the map, missing before tiled 1.0.1.
+    pub tiledversion: Option<Version>,
+    pub class: Option<String>,
 
     pub orientation: Orientation,
     pub renderorder: TileRenderOrder,
@@ -157,6 +243,16 @@ pub struct Map {
     pub tilewidth: u32,
     pub tileheight: u32,
 
+    pub infinite: bool,
+    pub parallaxoriginx: f32,
+    pub parallaxoriginy: f32,
+    /// Compression level used for tile layer data, `-1` means the default of
+    /// the compression algorithm.
+    pub compressionlevel: i32,
+
+    /// Ids for the next layer and object. For maps of old tiled versions that
+    /// do not store these, they are derived from the ids in use.
+    pub nextlayerid: u32,
     pub nextobjectid: u32,
     pub backgroundcolor: Option<Color>,
 
@@ -165,12 +261,21 @@ pub struct Map {
     pub tilesets: Vec<Tileset>,
 
     pub layers: Vec<MapLayer>,
+
+    /// The templates used by objects, by the path in their `template`
+    /// attribute. See `Map::load_templates`.
+    pub templates: ::std::collections::HashMap<String, Template>,
+    /// The project the map was loaded with, see `LoadOptions::project`.
+    pub project: Option<Project>,
+
+    /// Non-fatal problems that were encountered while loading the map.
+    pub warnings: Vec<LoadWarning>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
-    a: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
 }
@@ -1,27 +1,103 @@
+use std::collections::HashMap;
+use std::fmt;
+
 use serde::de;
 
-use super::{Color, ImageLayer, Layer, MapLayer, Objectgroup, Orientation, Properties, StaggerAxis,
-            StaggerIndex, TileRenderOrder, Tileset};
+use warnings;
+use {
+    Color, GroupLayer, ImageLayer, Layer, LoadWarning, Map, MapLayer, Objectgroup, Orientation,
+    Properties, StaggerAxis, StaggerIndex, TileRenderOrder, Tileset, Version,
+};
 
-#[derive(Debug, Deserialize)]
-enum AnyMapLayer {
-    #[serde(rename = "layer")]
+/// A child element of a map or group.
+#[derive(Debug)]
+pub(crate) enum AnyMapLayer {
     Layer(Layer),
-    #[serde(rename = "objectgroup")]
     ObjectGroup(Objectgroup),
-    #[serde(rename = "imagelayer")]
     ImageLayer(ImageLayer),
-    #[serde(rename = "properties", deserialize_with = "::properties::deserialize_properties")]
+    Group(GroupLayer),
     Properties(Option<Properties>),
-    #[serde(rename = "tileset")]
     Tileset(Tileset),
+    Skipped,
+}
+
+pub(crate) struct PropertiesElement(pub Option<Properties>);
+
+impl<'de> de::Deserialize<'de> for PropertiesElement {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PropertiesElement, D::Error> {
+        ::properties::deserialize_properties(deserializer).map(PropertiesElement)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for AnyMapLayer {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<AnyMapLayer, D::Error> {
+        const ELEMENTS: &[&str] = &[
+            "layer",
+            "objectgroup",
+            "imagelayer",
+            "group",
+            "properties",
+            "tileset",
+        ];
+
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = AnyMapLayer;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a layer, tileset or properties")
+            }
+
+            fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<AnyMapLayer, A::Error> {
+                use serde::de::VariantAccess;
+
+                let (name, variant): (String, _) = data.variant()?;
+
+                match &name[..] {
+                    "layer" => variant.newtype_variant().map(AnyMapLayer::Layer),
+                    "objectgroup" => variant.newtype_variant().map(AnyMapLayer::ObjectGroup),
+                    "imagelayer" => variant.newtype_variant().map(AnyMapLayer::ImageLayer),
+                    "group" => variant.newtype_variant().map(AnyMapLayer::Group),
+                    "properties" => variant
+                        .newtype_variant()
+                        .map(|p: PropertiesElement| AnyMapLayer::Properties(p.0)),
+                    "tileset" => variant.newtype_variant().map(AnyMapLayer::Tileset),
+                    // only relevant for the editor itself
+                    "editorsettings" => variant
+                        .newtype_variant::<de::IgnoredAny>()
+                        .map(|_| AnyMapLayer::Skipped),
+                    _ => {
+                        variant.newtype_variant::<de::IgnoredAny>()?;
+                        warnings::warn(LoadWarning::UnknownElement { name: name });
+                        Ok(AnyMapLayer::Skipped)
+                    }
+                }
+            }
+        }
+
+        deserializer.deserialize_enum("AnyMapLayer", ELEMENTS, Visitor)
+    }
+}
+
+fn default_renderorder() -> TileRenderOrder {
+    TileRenderOrder::RightDown
+}
+
+fn default_compressionlevel() -> i32 {
+    -1
 }
 
 #[derive(Debug, Deserialize)]
 struct MapImpl {
-    version: String,
+    version: Version,
+    tiledversion: Option<Version>,
+    class: Option<String>,
 
     orientation: Orientation,
+    #[serde(default = "default_renderorder")]
     renderorder: TileRenderOrder,
     hexsidelength: Option<i32>,
     staggeraxis: Option<StaggerAxis>,
@@ -32,15 +108,25 @@ struct MapImpl {
     tilewidth: u32,
     tileheight: u32,
 
-    nextobjectid: u32,
+    #[serde(deserialize_with = "::util::deserialize_bool", default)]
+    infinite: bool,
+    #[serde(default)]
+    parallaxoriginx: f32,
+    #[serde(default)]
+    parallaxoriginy: f32,
+    #[serde(default = "default_compressionlevel")]
+    compressionlevel: i32,
+
+    nextlayerid: Option<u32>,
+    nextobjectid: Option<u32>,
     backgroundcolor: Option<Color>,
 
     #[serde(rename = "$value")]
     layers: Vec<AnyMapLayer>,
 }
 
-impl<'de> de::Deserialize<'de> for super::Map {
-    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<super::Map, D::Error> {
+impl<'de> de::Deserialize<'de> for Map {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Map, D::Error> {
         let mapi: MapImpl = de::Deserialize::deserialize(deserializer)?;
 
         let mut layers = Vec::new();
@@ -60,11 +146,15 @@ impl<'de> de::Deserialize<'de> for super::Map {
                 AnyMapLayer::Layer(l) => layers.push(MapLayer::Layer(l)),
                 AnyMapLayer::ImageLayer(il) => layers.push(MapLayer::ImageLayer(il)),
                 AnyMapLayer::ObjectGroup(o) => layers.push(MapLayer::ObjectGroup(o)),
+                AnyMapLayer::Group(g) => layers.push(MapLayer::Group(g)),
+                AnyMapLayer::Skipped => {}
             }
         }
 
-        Ok(super::Map {
+        let mut map = Map {
             version: mapi.version,
+            tiledversion: mapi.tiledversion,
+            class: mapi.class,
 
             orientation: mapi.orientation,
             renderorder: mapi.renderorder,
@@ -77,12 +167,39 @@ impl<'de> de::Deserialize<'de> for super::Map {
             tilewidth: mapi.tilewidth,
             tileheight: mapi.tileheight,
 
-            nextobjectid: mapi.nextobjectid,
+            infinite: mapi.infinite,
+            parallaxoriginx: mapi.parallaxoriginx,
+            parallaxoriginy: mapi.parallaxoriginy,
+            compressionlevel: mapi.compressionlevel,
+
+            nextlayerid: mapi.nextlayerid.unwrap_or(0),
+            nextobjectid: mapi.nextobjectid.unwrap_or(0),
             backgroundcolor: mapi.backgroundcolor,
 
             layers: layers,
             properties: properties,
             tilesets: tilesets,
-        })
+
+            templates: HashMap::new(),
+            project: None,
+
+            warnings: Vec::new(),
+        };
+
+        // maps saved by old versions of tiled lack these, so derive them
+        // from the ids in use
+        if mapi.nextlayerid.is_none() {
+            map.nextlayerid = map.all_layers().map(|l| l.common().id).max().unwrap_or(0) + 1;
+        }
+        if mapi.nextobjectid.is_none() {
+            map.nextobjectid = map
+                .object_groups()
+                .flat_map(|o| o.objects.iter())
+                .map(|o| o.id)
+                .max()
+                .unwrap_or(0) + 1;
+        }
+
+        Ok(map)
     }
 }
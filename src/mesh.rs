@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use {Layer, Map};
+
+/// Number of `f32`s per vertex in `TileBatch::vertices`: the position `x, y`
+/// in pixels, followed by the texture coordinates `u, v`.
+pub const VERTEX_SIZE: usize = 4;
+
+/// The quads of all tiles of a layer that are drawn with the same texture.
+#[derive(Debug, Default)]
+pub struct TileBatch {
+    /// `firstgid` of the tileset, which identifies it within its map.
+    pub firstgid: u32,
+    /// For image-collection tilesets, the local id of the tile whose image is
+    /// used. Tiles of a single-image tileset share one batch.
+    pub tile: Option<u32>,
+
+    /// Interleaved vertices, see `VERTEX_SIZE`. Texture coordinates are
+    /// normalized to `0..1` over the whole image.
+    pub vertices: Vec<f32>,
+    /// Two triangles per quad, counter-clockwise in a y-down coordinate system.
+    pub indices: Vec<u32>,
+}
+
+impl Layer {
+    /// Builds a quad for each non-empty tile of the layer, batched by the
+    /// texture they use. The batches are ordered by tileset, and the quads in
+    /// each batch are in render order (see `tiles_in_render_order`).
+    ///
+    /// Positions are in pixels relative to the top-left corner of the map and
+    /// include the `tileoffset` of the tilesets, but not the offset or the
+    /// parallax factors of the layer, which are better applied as a transform.
+    /// The 120° rotation of tiles on hexagonal maps is not applied.
+    pub fn build_mesh(&self, map: &Map) -> Vec<TileBatch> {
+        let mut batches = BTreeMap::new();
+
+        for (x, y, gid) in self.tiles_in_render_order(map) {
+            let (tileset, local_id) = match map.tileset_for_gid(gid) {
+                Some(t) => t,
+                None => continue,
+            };
+            let (image, rect) = match (tileset.tile_image(local_id), tileset.tile_rect(local_id)) {
+                (Some(image), Some(rect)) => (image, rect),
+                _ => continue,
+            };
+
+            let key = (
+                tileset.firstgid,
+                if tileset.is_image_collection() {
+                    Some(local_id)
+                } else {
+                    None
+                },
+            );
+            let batch = batches.entry(key).or_insert_with(|| TileBatch {
+                firstgid: key.0,
+                tile: key.1,
+                ..TileBatch::default()
+            });
+
+            let (rect_x, rect_y, rect_width, rect_height) = rect;
+            let (width, height) = if gid.flipped_diagonally() {
+                (rect_height as f32, rect_width as f32)
+            } else {
+                (rect_width as f32, rect_height as f32)
+            };
+
+            // tiles are anchored at the bottom-left corner of their cell
+            let (px, py) = map.tile_to_pixel(x as i32, y as i32);
+            let left = px + tileset.tileoffset.x as f32;
+            let top = py + map.tileheight as f32 - height + tileset.tileoffset.y as f32;
+
+            let first_index = (batch.vertices.len() / VERTEX_SIZE) as u32;
+
+            for &(cu, cv) in [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)].iter() {
+                let (u, v) = gid.unflip(cu, cv);
+
+                batch.vertices.extend_from_slice(&[
+                    left + cu * width,
+                    top + cv * height,
+                    (rect_x as f32 + u * rect_width as f32) / image.width as f32,
+                    (rect_y as f32 + v * rect_height as f32) / image.height as f32,
+                ]);
+            }
+
+            batch
+                .indices
+                .extend([0, 1, 2, 0, 2, 3].iter().map(|i| first_index + i));
+        }
+
+        batches.into_iter().map(|(_, batch)| batch).collect()
+    }
+}
@@ -0,0 +1,510 @@
+use std::collections::{BinaryHeap, HashMap};
+
+use geometry::{distance, turn, Point, ELLIPSE_SEGMENTS};
+use search::Candidate;
+use {Map, Objectgroup};
+
+/// Tolerance for comparing positions, in pixels.
+const EPSILON: f32 = 1e-3;
+
+/// A convex region of a `NavMesh`.
+#[derive(Clone, Debug)]
+pub struct NavPolygon {
+    /// Corners of the polygon, clockwise as seen on screen with the y axis
+    /// pointing down.
+    pub vertices: Vec<Point>,
+    pub portals: Vec<Portal>,
+}
+
+/// The part of the border of a `NavPolygon` that is shared with a neighbour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Portal {
+    /// Index of the neighbour in `NavMesh::polygons`.
+    pub polygon: usize,
+    /// The end points of the shared segment, as seen when leaving the
+    /// polygon through it.
+    pub left: Point,
+    pub right: Point,
+}
+
+/// Walkable areas of a map, split into convex polygons for path queries.
+///
+/// Positions are in the same space as `Object::x` and `Object::y`.
+#[derive(Clone, Debug, Default)]
+pub struct NavMesh {
+    pub polygons: Vec<NavPolygon>,
+}
+
+impl Map {
+    /// Builds a navigation mesh from the object group with the given name,
+    /// see `NavMesh::from_objectgroup`.
+    pub fn nav_mesh(&self, group_name: &str) -> Option<NavMesh> {
+        self.object_groups()
+            .find(|o| o.common.name == group_name)
+            .map(|group| NavMesh::from_objectgroup(self, group))
+    }
+}
+
+impl NavMesh {
+    /// Builds a navigation mesh from the polygon objects of a group, which
+    /// mark walkable areas, minus its rectangle, ellipse and tile objects,
+    /// which mark obstacles. Tile objects block the rectangle given by their
+    /// width and height, or by the size of their tile in `map` if they have
+    /// none. Polylines, points and text objects are skipped.
+    ///
+    /// Walkable polygons may share edges, but should not overlap.
+    pub fn from_objectgroup(map: &Map, group: &Objectgroup) -> NavMesh {
+        let mut pieces = Vec::new();
+        let mut obstacles = Vec::new();
+
+        for object in &group.objects {
+            let is_obstacle =
+                object.ellipse.is_some() || object.gid.is_some() || object.is_rectangle();
+            if object.polygon.is_none() && !is_obstacle {
+                continue;
+            }
+
+            let mut outline = object.vertices(map, ELLIPSE_SEGMENTS);
+            let area = signed_area(&outline);
+
+            if area == 0.0 {
+                continue;
+            } else if area < 0.0 {
+                outline.reverse();
+            }
+
+            if object.polygon.is_some() {
+                pieces.extend(merge_convex(triangulate(outline)));
+            } else {
+                obstacles.push(outline);
+            }
+        }
+
+        for obstacle in &obstacles {
+            pieces = pieces
+                .into_iter()
+                .flat_map(|piece| subtract_convex(piece, obstacle))
+                .collect();
+        }
+
+        let mut polygons: Vec<NavPolygon> = merge_convex(pieces)
+            .into_iter()
+            .map(|vertices| NavPolygon {
+                vertices: remove_straight_corners(vertices),
+                portals: Vec::new(),
+            })
+            .collect();
+
+        for i in 0..polygons.len() {
+            for j in i + 1..polygons.len() {
+                for (left, right) in shared_segments(&polygons[i].vertices, &polygons[j].vertices) {
+                    polygons[i].portals.push(Portal {
+                        polygon: j,
+                        left: left,
+                        right: right,
+                    });
+                    polygons[j].portals.push(Portal {
+                        polygon: i,
+                        left: right,
+                        right: left,
+                    });
+                }
+            }
+        }
+
+        NavMesh { polygons: polygons }
+    }
+
+    /// Returns the index of the polygon containing the given point.
+    pub fn find_polygon(&self, point: Point) -> Option<usize> {
+        self.polygons
+            .iter()
+            .position(|p| convex_contains(&p.vertices, point))
+    }
+
+    /// Finds a short path between two points, both of which have to be inside
+    /// of the mesh. The polygons to walk through are found with A* between
+    /// their centers, and the path through them is straightened with the
+    /// funnel algorithm.
+    pub fn find_path(&self, start: Point, goal: Point) -> Option<Vec<Point>> {
+        let first = self.find_polygon(start)?;
+        let last = self.find_polygon(goal)?;
+
+        let corridor = self.find_corridor(first, last, goal)?;
+
+        // the portals along the corridor, framed by the start and the goal
+        let mut portals = vec![(start, start)];
+        for pair in corridor.windows(2) {
+            let portal = self.polygons[pair[0]]
+                .portals
+                .iter()
+                .find(|p| p.polygon == pair[1])
+                .expect("consecutive polygons of a corridor are neighbours");
+            portals.push((portal.left, portal.right));
+        }
+        portals.push((goal, goal));
+
+        Some(funnel(&portals))
+    }
+
+    fn find_corridor(&self, first: usize, last: usize, goal: Point) -> Option<Vec<usize>> {
+        let centers: Vec<Point> = self
+            .polygons
+            .iter()
+            .map(|p| centroid(&p.vertices))
+            .collect();
+
+        let mut open = BinaryHeap::new();
+        let mut came_from = HashMap::new();
+        let mut best = HashMap::new();
+
+        best.insert(first, 0.0);
+        open.push(Candidate {
+            estimate: distance(centers[first], goal),
+            cost: 0.0,
+            node: first,
+        });
+
+        while let Some(Candidate { cost, node: polygon, .. }) = open.pop() {
+            if polygon == last {
+                let mut corridor = vec![polygon];
+                let mut current = polygon;
+
+                while let Some(&previous) = came_from.get(&current) {
+                    corridor.push(previous);
+                    current = previous;
+                }
+
+                corridor.reverse();
+                return Some(corridor);
+            }
+
+            if best.get(&polygon).map_or(false, |&c| cost > c) {
+                continue;
+            }
+
+            for portal in &self.polygons[polygon].portals {
+                let next = portal.polygon;
+                let new_cost = cost + distance(centers[polygon], centers[next]);
+
+                if best.get(&next).map_or(true, |&c| new_cost < c) {
+                    best.insert(next, new_cost);
+                    came_from.insert(next, polygon);
+                    open.push(Candidate {
+                        estimate: new_cost + distance(centers[next], goal),
+                        cost: new_cost,
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn same_point(a: Point, b: Point) -> bool {
+    (a.0 - b.0).abs() < EPSILON && (a.1 - b.1).abs() < EPSILON
+}
+
+/// Twice the enclosed area, positive for clockwise polygons.
+fn signed_area(points: &[Point]) -> f32 {
+    (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum()
+}
+
+fn centroid(points: &[Point]) -> Point {
+    let n = points.len() as f32;
+    let (x, y) = points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), p| (x + p.0, y + p.1));
+    (x / n, y / n)
+}
+
+fn convex_contains(polygon: &[Point], point: Point) -> bool {
+    (0..polygon.len()).all(|i| {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        turn(a, b, point) >= -EPSILON * distance(a, b)
+    })
+}
+
+/// Whether the polygon is convex and winds around once. Straight corners are
+/// allowed, but not ones that turn back.
+fn is_convex(polygon: &[Point]) -> bool {
+    let n = polygon.len();
+    let mut turning = 0.0;
+
+    for i in 0..n {
+        let (a, b, c) = (polygon[i], polygon[(i + 1) % n], polygon[(i + 2) % n]);
+        let turn = turn(a, b, c);
+        let dot = (b.0 - a.0) * (c.0 - b.0) + (b.1 - a.1) * (c.1 - b.1);
+
+        if turn < -EPSILON || (turn <= EPSILON && dot <= 0.0) {
+            return false;
+        }
+
+        turning += turn.atan2(dot);
+    }
+
+    turning < 2.0 * ::std::f32::consts::PI + 1e-3
+}
+
+/// Removes corners where the polygon goes straight on.
+fn remove_straight_corners(mut polygon: Vec<Point>) -> Vec<Point> {
+    let mut i = 0;
+
+    while i < polygon.len() && polygon.len() > 3 {
+        let n = polygon.len();
+        let (a, b, c) = (polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]);
+
+        if same_point(a, b) || (turn(a, b, c) / distance(a, c)).abs() <= EPSILON {
+            polygon.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    polygon
+}
+
+/// Splits a simple clockwise polygon into triangles by ear clipping.
+fn triangulate(mut polygon: Vec<Point>) -> Vec<Vec<Point>> {
+    let mut triangles = Vec::new();
+
+    while polygon.len() > 3 {
+        let n = polygon.len();
+        let corner = |i: usize| (polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]);
+
+        let is_ear = |i: usize| {
+            let (a, b, c) = corner(i);
+
+            turn(a, b, c) > 0.0
+                && (0..n).all(|j| {
+                    let p = polygon[j];
+                    same_point(p, a)
+                        || same_point(p, b)
+                        || same_point(p, c)
+                        || !(turn(a, b, p) >= 0.0 && turn(b, c, p) >= 0.0 && turn(c, a, p) >= 0.0)
+                })
+        };
+
+        // degenerate polygons might have no proper ear left, so fall back to
+        // any convex corner, or just the first one
+        let ear = (0..n)
+            .find(|&i| is_ear(i))
+            .or_else(|| {
+                (0..n).find(|&i| {
+                    let (a, b, c) = corner(i);
+                    turn(a, b, c) >= 0.0
+                })
+            })
+            .unwrap_or(0);
+
+        let (a, b, c) = corner(ear);
+        if turn(a, b, c) > EPSILON {
+            triangles.push(vec![a, b, c]);
+        }
+        polygon.remove(ear);
+    }
+
+    if polygon.len() == 3 && turn(polygon[0], polygon[1], polygon[2]) > EPSILON {
+        triangles.push(polygon);
+    }
+
+    triangles
+}
+
+/// Merges convex polygons that share an edge, as long as the result stays
+/// convex.
+fn merge_convex(mut polygons: Vec<Vec<Point>>) -> Vec<Vec<Point>> {
+    let mut merged_any = true;
+
+    while merged_any {
+        merged_any = false;
+
+        'search: for i in 0..polygons.len() {
+            for j in i + 1..polygons.len() {
+                if let Some(merged) = merge_pair(&polygons[i], &polygons[j]) {
+                    polygons[i] = merged;
+                    polygons.swap_remove(j);
+                    merged_any = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    polygons
+}
+
+fn merge_pair(p: &[Point], q: &[Point]) -> Option<Vec<Point>> {
+    let (n, m) = (p.len(), q.len());
+
+    for k in 0..n {
+        let (a, b) = (p[k], p[(k + 1) % n]);
+
+        let shared = (0..m).find(|&l| same_point(q[l], b) && same_point(q[(l + 1) % m], a));
+
+        if let Some(l) = shared {
+            // walk around p from b to a, then around q back to b
+            let mut merged: Vec<Point> = (0..n).map(|i| p[(k + 1 + i) % n]).collect();
+            merged.extend((2..m).map(|i| q[(l + i) % m]));
+
+            return if is_convex(&merged) {
+                Some(merged)
+            } else {
+                None
+            };
+        }
+    }
+
+    None
+}
+
+/// Clips a convex polygon to the side of the line from `a` to `b` where
+/// `cross` has the given sign.
+fn clip(polygon: &[Point], a: Point, b: Point, sign: f32) -> Vec<Point> {
+    let mut clipped = Vec::new();
+
+    for i in 0..polygon.len() {
+        let (p, q) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        let (fp, fq) = (sign * turn(a, b, p), sign * turn(a, b, q));
+
+        if fp >= 0.0 {
+            clipped.push(p);
+        }
+        if (fp >= 0.0) != (fq >= 0.0) {
+            let t = fp / (fp - fq);
+            clipped.push((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+        }
+    }
+
+    clipped
+}
+
+/// Subtracts a convex obstacle from a convex polygon, which leaves convex
+/// pieces: the parts outside of each edge of the obstacle, but inside of the
+/// edges before it.
+fn subtract_convex(polygon: Vec<Point>, obstacle: &[Point]) -> Vec<Vec<Point>> {
+    let mut pieces = Vec::new();
+    let mut remaining = polygon;
+
+    for i in 0..obstacle.len() {
+        let (a, b) = (obstacle[i], obstacle[(i + 1) % obstacle.len()]);
+
+        let outside = clip(&remaining, a, b, -1.0);
+        if outside.len() >= 3 && signed_area(&outside) > EPSILON {
+            pieces.push(outside);
+        }
+
+        remaining = clip(&remaining, a, b, 1.0);
+        if remaining.len() < 3 || signed_area(&remaining) <= EPSILON {
+            // the rest of the polygon is outside of the obstacle
+            return pieces;
+        }
+    }
+
+    pieces
+}
+
+/// Finds the segments where edges of `p` and `q` overlap, in the direction of
+/// the edges of `p`.
+fn shared_segments(p: &[Point], q: &[Point]) -> Vec<(Point, Point)> {
+    let mut segments = Vec::new();
+
+    for i in 0..p.len() {
+        let (a, b) = (p[i], p[(i + 1) % p.len()]);
+        let length = distance(a, b);
+        if length < EPSILON {
+            continue;
+        }
+
+        for j in 0..q.len() {
+            let (c, d) = (q[j], q[(j + 1) % q.len()]);
+
+            // the edges have to lie on the same line, in opposite directions
+            if (turn(a, b, c) / length).abs() > EPSILON || (turn(a, b, d) / length).abs() > EPSILON
+            {
+                continue;
+            }
+            let direction = (b.0 - a.0) * (d.0 - c.0) + (b.1 - a.1) * (d.1 - c.1);
+            if direction >= 0.0 {
+                continue;
+            }
+
+            let param = |r: Point| {
+                ((r.0 - a.0) * (b.0 - a.0) + (r.1 - a.1) * (b.1 - a.1)) / (length * length)
+            };
+            let start = param(d).max(0.0);
+            let end = param(c).min(1.0);
+
+            if (end - start) * length > EPSILON {
+                let at = |t: f32| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+                segments.push((at(start), at(end)));
+            }
+        }
+    }
+
+    segments
+}
+
+/// Straightens a path through a sequence of portals `(left, right)`, see
+/// "Simple Stupid Funnel Algorithm" by Mikko Mononen.
+fn funnel(portals: &[(Point, Point)]) -> Vec<Point> {
+    let mut path = vec![portals[0].0];
+
+    let mut apex = portals[0].0;
+    let (mut left, mut right) = portals[0];
+    let (mut left_index, mut right_index) = (0, 0);
+
+    let mut i = 1;
+    while i < portals.len() {
+        let (new_left, new_right) = portals[i];
+
+        // tighten the right side of the funnel
+        if turn(apex, right, new_right) <= 0.0 {
+            if same_point(apex, right) || turn(apex, left, new_right) > 0.0 {
+                right = new_right;
+                right_index = i;
+            } else {
+                // the right side crossed the left one, which becomes a corner
+                apex = left;
+                path.push(apex);
+
+                right = apex;
+                right_index = left_index;
+                i = left_index + 1;
+                continue;
+            }
+        }
+
+        // tighten the left side of the funnel
+        if turn(apex, left, new_left) >= 0.0 {
+            if same_point(apex, left) || turn(apex, right, new_left) < 0.0 {
+                left = new_left;
+                left_index = i;
+            } else {
+                apex = right;
+                path.push(apex);
+
+                left = apex;
+                left_index = right_index;
+                i = right_index + 1;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    let goal = portals[portals.len() - 1].0;
+    if !path.last().map_or(false, |&p| same_point(p, goal)) {
+        path.push(goal);
+    }
+
+    path
+}
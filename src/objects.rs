@@ -1,7 +1,7 @@
 use serde::de;
 use regex::Regex;
 
-use {Color, Properties};
+use {Color, LayerCommon, Properties};
 
 #[derive(Debug, Deserialize)]
 pub struct Ellipse;
@@ -20,13 +20,53 @@ pub struct Polygon {
     pub points: Vec<(f32, f32)>,
 }
 
+fn default_pixelsize() -> u32 {
+    16
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The content of a text object, which is drawn inside the bounds of the
+/// object.
+#[derive(Debug, Deserialize)]
+pub struct Text {
+    pub fontfamily: Option<String>,
+    #[serde(default = "default_pixelsize")]
+    pub pixelsize: u32,
+    #[serde(deserialize_with = "::util::deserialize_bool", default)]
+    pub wrap: bool,
+    pub color: Option<Color>,
+    #[serde(deserialize_with = "::util::deserialize_bool", default)]
+    pub bold: bool,
+    #[serde(deserialize_with = "::util::deserialize_bool", default)]
+    pub italic: bool,
+    #[serde(deserialize_with = "::util::deserialize_bool", default)]
+    pub underline: bool,
+    #[serde(deserialize_with = "::util::deserialize_bool", default)]
+    pub strikeout: bool,
+    #[serde(
+        deserialize_with = "::util::deserialize_bool",
+        default = "default_true"
+    )]
+    pub kerning: bool,
+    pub halign: Option<String>,
+    pub valign: Option<String>,
+
+    #[serde(rename = "$value", default)]
+    pub text: String,
+}
+
 fn deserialize_points<'de, D: de::Deserializer<'de>>(
     deserializer: D,
 ) -> Result<Vec<(f32, f32)>, D::Error> {
     let points_str: String = de::Deserialize::deserialize(deserializer)?;
 
     lazy_static! {
-        static ref POINTS_REGEX: Regex = Regex::new(r"((-?\d+),(-?\d+))").unwrap();
+        static ref POINTS_REGEX: Regex =
+            Regex::new(r"((-?\d+(?:\.\d+)?(?:e[-+]?\d+)?),(-?\d+(?:\.\d+)?(?:e[-+]?\d+)?))")
+                .unwrap();
     }
 
     POINTS_REGEX
@@ -54,12 +94,18 @@ fn deserialize_points<'de, D: de::Deserializer<'de>>(
 
 #[derive(Debug, Deserialize)]
 pub struct Object {
+    /// The id is missing for objects in templates, and is then 0.
+    #[serde(default)]
     pub id: u32,
     pub name: Option<String>,
+    /// The class of the object, called type before tiled 1.9.
     #[serde(rename(deserialize = "type"))]
     pub type_: Option<String>,
+    pub class: Option<String>,
     pub gid: Option<u32>,
+    #[serde(default)]
     pub x: f32,
+    #[serde(default)]
     pub y: f32,
     pub width: Option<f32>,
     pub height: Option<f32>,
@@ -70,24 +116,30 @@ pub struct Object {
     pub properties: Option<Properties>,
 
     pub ellipse: Option<()>,
+    pub point: Option<()>,
     pub polyline: Option<Polyline>,
     pub polygon: Option<Polygon>,
+    pub text: Option<Text>,
+
+    /// The path of the template the object is an instance of, see
+    /// `Map::templates`.
+    pub template: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Object {
+    /// The class of the object, from `class` or from `type` for maps saved
+    /// before tiled 1.9.
+    pub fn class_name(&self) -> Option<&str> {
+        self.class.as_ref().or(self.type_.as_ref()).map(|c| &c[..])
+    }
+}
+
+#[derive(Debug)]
 pub struct Objectgroup {
-    pub name: String,
+    pub common: LayerCommon,
+
     pub draworder: Option<String>,
-    pub visible: Option<u8>,
-    pub opacity: Option<f32>,
-    pub offsetx: Option<f32>,
-    pub offsety: Option<f32>,
     pub color: Option<Color>,
 
-    #[serde(deserialize_with = "::properties::deserialize_properties")]
-    #[serde(default)]
-    pub properties: Option<Properties>,
-
-    #[serde(rename(deserialize = "object"), default)]
     pub objects: Vec<Object>,
 }
@@ -0,0 +1,328 @@
+use std::collections::{BinaryHeap, HashMap};
+
+use coords::StaggerParams;
+use search::Candidate;
+use {HexGrid, Layer, Map, MapLayer, Orientation, Tileset};
+
+/// Which tiles of orthogonal, isometric and staggered maps are neighbours.
+/// Tiles of hexagonal maps always have 6 neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Only tiles sharing an edge.
+    Four,
+    /// Tiles sharing an edge or a corner. Moving diagonally costs `√2` times
+    /// as much, and is only possible if both tiles next to the move are
+    /// passable.
+    Eight,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Topology {
+    Square,
+    Staggered(StaggerParams),
+    Hexagonal(HexGrid),
+}
+
+/// The cost of moving onto each tile of a map, for finding paths with A*.
+#[derive(Clone, Debug)]
+pub struct NavGrid {
+    pub width: u32,
+    pub height: u32,
+    pub connectivity: Connectivity,
+
+    topology: Topology,
+    /// `None` for impassable tiles.
+    costs: Vec<Option<f32>>,
+}
+
+impl Map {
+    /// Builds a navigation grid of the size of the map, using the tile
+    /// layers accepted by `layer_filter`.
+    ///
+    /// `cost` gets the tileset and local id of a tile, and returns the cost of
+    /// moving onto it, or `None` if it is impassable (see
+    /// `NavGrid::property_cost`). If several layers have a tile at the same
+    /// position, the highest cost is used. Positions without any tile cost
+    /// `1`.
+    pub fn nav_grid<F, C>(
+        &self,
+        mut layer_filter: F,
+        mut cost: C,
+        connectivity: Connectivity,
+    ) -> NavGrid
+    where
+        F: FnMut(&Layer) -> bool,
+        C: FnMut(&Tileset, u32) -> Option<f32>,
+    {
+        let topology = match self.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => Topology::Square,
+            Orientation::Staggered => Topology::Staggered(StaggerParams::new(self)),
+            Orientation::Hexagonal => {
+                Topology::Hexagonal(self.hex_grid().expect("hexagonal map has a hex grid"))
+            }
+        };
+
+        let mut costs = vec![Some(1.0); self.width as usize * self.height as usize];
+        let mut has_tile = vec![false; costs.len()];
+
+        for layer in self.all_layers() {
+            let layer = match *layer {
+                MapLayer::Layer(ref l) if layer_filter(l) => l,
+                _ => continue,
+            };
+
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let i = (y * self.width + x) as usize;
+
+                    let tile_cost = match layer
+                        .tile_gid(x, y)
+                        .and_then(|gid| self.tileset_for_gid(gid))
+                    {
+                        Some((tileset, local_id)) => cost(tileset, local_id),
+                        None => continue,
+                    };
+
+                    costs[i] = match (has_tile[i], costs[i], tile_cost) {
+                        (false, _, c) => c,
+                        (true, Some(a), Some(b)) => Some(a.max(b)),
+                        _ => None,
+                    };
+                    has_tile[i] = true;
+                }
+            }
+        }
+
+        NavGrid {
+            width: self.width,
+            height: self.height,
+            connectivity: connectivity,
+            topology: topology,
+            costs: costs,
+        }
+    }
+}
+
+impl NavGrid {
+    /// Returns the cost of a tile from its properties: `None` if it has a
+    /// `solid` property that is `true`, the value of its `cost` property if it
+    /// is a number, and `1` otherwise.
+    pub fn property_cost(tileset: &Tileset, local_id: u32) -> Option<f32> {
+        let properties = match tileset.tile(local_id).and_then(|t| t.properties.as_ref()) {
+            Some(p) => p,
+            None => return Some(1.0),
+        };
+
+        if properties.get_or("solid", false).unwrap_or(false) {
+            return None;
+        }
+
+        Some(properties.get_or("cost", 1.0).unwrap_or(1.0) as f32)
+    }
+
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        if x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cost of moving onto the tile at `(x, y)`, or `None` if it
+    /// is impassable or outside of the grid.
+    pub fn cost(&self, x: u32, y: u32) -> Option<f32> {
+        self.index((x as i32, y as i32)).and_then(|i| self.costs[i])
+    }
+
+    /// Changes the cost of a tile, e.g. for doors or units blocking the way.
+    pub fn set_cost(&mut self, x: u32, y: u32, cost: Option<f32>) {
+        if let Some(i) = self.index((x as i32, y as i32)) {
+            self.costs[i] = cost;
+        }
+    }
+
+    fn passable(&self, tile: (i32, i32)) -> bool {
+        self.index(tile).map_or(false, |i| self.costs[i].is_some())
+    }
+
+    /// Whether a tile inside of the grid is impassable.
+    fn blocked(&self, tile: (i32, i32)) -> bool {
+        self.index(tile).map_or(false, |i| self.costs[i].is_none())
+    }
+
+    /// The neighbours of a tile that share an edge with it.
+    fn edge_neighbors(&self, (x, y): (i32, i32)) -> Vec<(i32, i32)> {
+        match self.topology {
+            Topology::Square => vec![(x + 1, y), (x, y + 1), (x - 1, y), (x, y - 1)],
+            Topology::Staggered(params) => {
+                // the neighbours are in the adjacent rows (or columns), which
+                // are shifted by half a tile
+                if params.stagger_x {
+                    let y0 = if params.is_staggered(x) { y } else { y - 1 };
+                    vec![(x + 1, y0), (x + 1, y0 + 1), (x - 1, y0), (x - 1, y0 + 1)]
+                } else {
+                    let x0 = if params.is_staggered(y) { x } else { x - 1 };
+                    vec![(x0, y + 1), (x0 + 1, y + 1), (x0, y - 1), (x0 + 1, y - 1)]
+                }
+            }
+            Topology::Hexagonal(grid) => grid.neighbors((x, y)).to_vec(),
+        }
+    }
+
+    /// The neighbours of a tile that only share a corner with it.
+    fn corner_neighbors(&self, (x, y): (i32, i32)) -> Vec<(i32, i32)> {
+        match self.topology {
+            Topology::Square => vec![
+                (x + 1, y + 1),
+                (x - 1, y + 1),
+                (x - 1, y - 1),
+                (x + 1, y - 1),
+            ],
+            Topology::Staggered(params) => {
+                if params.stagger_x {
+                    vec![(x + 2, y), (x - 2, y), (x, y + 1), (x, y - 1)]
+                } else {
+                    vec![(x + 1, y), (x - 1, y), (x, y + 2), (x, y - 2)]
+                }
+            }
+            Topology::Hexagonal(_) => Vec::new(),
+        }
+    }
+
+    /// Returns the passable neighbours of a tile, with the cost of moving
+    /// onto them.
+    fn neighbors(&self, tile: (i32, i32)) -> Vec<((i32, i32), f32)> {
+        let mut neighbors: Vec<_> = self
+            .edge_neighbors(tile)
+            .into_iter()
+            .filter_map(|n| self.index(n).and_then(|i| self.costs[i]).map(|c| (n, c)))
+            .collect();
+
+        if self.connectivity == Connectivity::Eight {
+            let edges = self.edge_neighbors(tile);
+
+            for n in self.corner_neighbors(tile) {
+                let cost = match self.index(n).and_then(|i| self.costs[i]) {
+                    Some(c) => c * ::std::f32::consts::SQRT_2,
+                    None => continue,
+                };
+
+                // don't cut corners: the tiles next to the move, which are
+                // edge neighbours of both ends, must not be impassable. At
+                // the border of staggered maps, one of them can be missing.
+                let blocked = self
+                    .edge_neighbors(n)
+                    .into_iter()
+                    .any(|m| edges.contains(&m) && self.blocked(m));
+
+                if !blocked {
+                    neighbors.push((n, cost));
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// Position of a tile in a grid where edge neighbours are one step apart
+    /// along an axis, and corner neighbours one step along each axis.
+    fn grid_position(&self, (x, y): (i32, i32)) -> (f32, f32) {
+        match self.topology {
+            Topology::Staggered(params) => {
+                let (cx, cy) = if params.stagger_x {
+                    let shift = if params.is_staggered(x) { 0.5 } else { 0.0 };
+                    (x as f32 * 0.5, y as f32 + shift)
+                } else {
+                    let shift = if params.is_staggered(y) { 0.5 } else { 0.0 };
+                    (x as f32 + shift, y as f32 * 0.5)
+                };
+
+                (cx + cy, cy - cx)
+            }
+            _ => (x as f32, y as f32),
+        }
+    }
+
+    /// A lower bound for the number of steps between two tiles, weighted like
+    /// the costs of the moves.
+    fn steps(&self, a: (i32, i32), b: (i32, i32)) -> f32 {
+        if let Topology::Hexagonal(grid) = self.topology {
+            return grid.distance(a, b) as f32;
+        }
+
+        let (ax, ay) = self.grid_position(a);
+        let (bx, by) = self.grid_position(b);
+        let (dx, dy) = ((ax - bx).abs(), (ay - by).abs());
+
+        match self.connectivity {
+            Connectivity::Four => dx + dy,
+            Connectivity::Eight => dx.max(dy) + (::std::f32::consts::SQRT_2 - 1.0) * dx.min(dy),
+        }
+    }
+
+    /// Finds the cheapest path between two tiles with A*, including both of
+    /// them. Returns `None` if either is impassable, or there is no path.
+    pub fn find_path(&self, start: (u32, u32), goal: (u32, u32)) -> Option<Vec<(u32, u32)>> {
+        let start = (start.0 as i32, start.1 as i32);
+        let goal = (goal.0 as i32, goal.1 as i32);
+
+        if !self.passable(start) || !self.passable(goal) {
+            return None;
+        }
+
+        // scales the heuristic, so it never overestimates
+        let min_cost = self
+            .costs
+            .iter()
+            .filter_map(|&c| c)
+            .fold(::std::f32::INFINITY, f32::min)
+            .max(0.0);
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+        let mut best = HashMap::new();
+
+        best.insert(start, 0.0);
+        open.push(Candidate {
+            estimate: self.steps(start, goal) * min_cost,
+            cost: 0.0,
+            node: start,
+        });
+
+        while let Some(Candidate { cost, node: tile, .. }) = open.pop() {
+            if tile == goal {
+                let mut path = vec![(tile.0 as u32, tile.1 as u32)];
+                let mut current = tile;
+
+                while let Some(&previous) = came_from.get(&current) {
+                    path.push((previous.0 as u32, previous.1 as u32));
+                    current = previous;
+                }
+
+                path.reverse();
+                return Some(path);
+            }
+
+            // skip outdated entries
+            if best.get(&tile).map_or(false, |&c| cost > c) {
+                continue;
+            }
+
+            for (neighbor, step_cost) in self.neighbors(tile) {
+                let new_cost = cost + step_cost;
+
+                if best.get(&neighbor).map_or(true, |&c| new_cost < c) {
+                    best.insert(neighbor, new_cost);
+                    came_from.insert(neighbor, tile);
+                    open.push(Candidate {
+                        estimate: new_cost + self.steps(neighbor, goal) * min_cost,
+                        cost: new_cost,
+                        node: neighbor,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,411 @@
+use std::fs::File;
+use std::path::Path;
+
+use serde::de::Error;
+use serde_json::{self, Value};
+
+use properties::color_value;
+use {LoadWarning, Map, MapLayer, Objectgroup, Properties, Property};
+
+pub type JsonError = serde_json::Error;
+
+/// A tiled project file (`.tiled-project`). Only the custom property types
+/// are loaded.
+///
+/// Pass it to the map loader with `LoadOptions::project`, to fill in the
+/// default values of class members and to check enum values.
+#[derive(Clone, Debug, Default)]
+pub struct Project {
+    pub property_types: Vec<PropertyType>,
+}
+
+/// A custom property type defined in a project.
+#[derive(Clone, Debug)]
+pub enum PropertyType {
+    Class(ClassType),
+    Enum(EnumType),
+}
+
+#[derive(Clone, Debug)]
+pub struct ClassType {
+    pub id: u32,
+    pub name: String,
+    /// What the class can be used for, like `"property"` or `"object"`.
+    pub use_as: Vec<String>,
+    /// The members of the class with their default values.
+    pub members: Properties,
+}
+
+/// How the values of an enum are stored in properties.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnumStorage {
+    /// The name of the value, in a string property.
+    String,
+    /// The index of the value, in an int property.
+    Int,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnumType {
+    pub id: u32,
+    pub name: String,
+    pub storage: EnumStorage,
+    pub values: Vec<String>,
+    /// Whether a property can hold several values, as a comma separated list
+    /// of names or as bit flags.
+    pub values_as_flags: bool,
+}
+
+impl EnumType {
+    /// Whether the property holds a value of this enum.
+    pub fn is_valid(&self, property: &Property) -> bool {
+        match (self.storage, property) {
+            (EnumStorage::String, &Property::String(ref value)) => {
+                if self.values_as_flags {
+                    value.is_empty() || value.split(',').all(|v| self.values.iter().any(|n| n == v))
+                } else {
+                    self.values.contains(value)
+                }
+            }
+            (EnumStorage::Int, &Property::Int(value)) => {
+                if self.values_as_flags {
+                    value >= 0 && (self.values.len() >= 63 || value < 1 << self.values.len())
+                } else {
+                    value >= 0 && (value as u64) < self.values.len() as u64
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectImpl {
+    #[serde(rename = "propertyTypes", default)]
+    property_types: Vec<RawPropertyType>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPropertyType {
+    id: u32,
+    name: String,
+    #[serde(rename = "type")]
+    type_: String,
+    #[serde(rename = "useAs", default)]
+    use_as: Vec<String>,
+    #[serde(default)]
+    members: Vec<RawMember>,
+    #[serde(rename = "storageType", default)]
+    storage_type: String,
+    #[serde(default)]
+    values: Vec<String>,
+    #[serde(rename = "valuesAsFlags", default)]
+    values_as_flags: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMember {
+    name: String,
+    #[serde(rename = "type")]
+    type_: String,
+    #[serde(rename = "propertyType", default)]
+    property_type: String,
+    #[serde(default)]
+    value: Value,
+}
+
+impl Project {
+    pub fn load_from_str(project_str: &str) -> Result<Project, JsonError> {
+        let raw: ProjectImpl = serde_json::from_str(project_str)?;
+        Project::from_raw(raw).map_err(JsonError::custom)
+    }
+
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Project, JsonError> {
+        let file = File::open(path).map_err(JsonError::io)?;
+        let raw: ProjectImpl = serde_json::from_reader(file)?;
+        Project::from_raw(raw).map_err(JsonError::custom)
+    }
+
+    fn from_raw(raw: ProjectImpl) -> Result<Project, String> {
+        let mut property_types = Vec::new();
+
+        for raw_type in &raw.property_types {
+            let property_type = match &raw_type.type_[..] {
+                "class" => {
+                    let mut members = Properties::new();
+
+                    for member in &raw_type.members {
+                        let value = convert_value(
+                            &member.type_,
+                            &member.property_type,
+                            &member.value,
+                            &raw.property_types,
+                        )
+                        .map_err(|e| {
+                            format!("member '{}' of '{}': {}", member.name, raw_type.name, e)
+                        })?;
+
+                        members.insert_typed(
+                            member.name.clone(),
+                            value,
+                            non_empty(&member.property_type),
+                        );
+                    }
+
+                    PropertyType::Class(ClassType {
+                        id: raw_type.id,
+                        name: raw_type.name.clone(),
+                        use_as: raw_type.use_as.clone(),
+                        members: members,
+                    })
+                }
+                "enum" => PropertyType::Enum(EnumType {
+                    id: raw_type.id,
+                    name: raw_type.name.clone(),
+                    storage: match &raw_type.storage_type[..] {
+                        "int" => EnumStorage::Int,
+                        _ => EnumStorage::String,
+                    },
+                    values: raw_type.values.clone(),
+                    values_as_flags: raw_type.values_as_flags,
+                }),
+                t => return Err(format!("unexpected custom type kind: '{}'", t)),
+            };
+
+            property_types.push(property_type);
+        }
+
+        Ok(Project {
+            property_types: property_types,
+        })
+    }
+
+    /// Looks up a custom property type by name.
+    pub fn property_type(&self, name: &str) -> Option<&PropertyType> {
+        self.property_types.iter().find(|t| match **t {
+            PropertyType::Class(ref c) => c.name == name,
+            PropertyType::Enum(ref e) => e.name == name,
+        })
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassType> {
+        match self.property_type(name) {
+            Some(&PropertyType::Class(ref c)) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        match self.property_type(name) {
+            Some(&PropertyType::Enum(ref e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Fills in the members that class properties omit, because they have
+    /// their default value, and checks the values of enum properties.
+    pub(crate) fn apply(&self, properties: &mut Properties, warnings: &mut Vec<LoadWarning>) {
+        self.apply_nested(properties, warnings, &mut Vec::new());
+    }
+
+    /// `classes` are the classes being filled in, so classes that contain
+    /// themselves are not expanded forever.
+    fn apply_nested(
+        &self,
+        properties: &mut Properties,
+        warnings: &mut Vec<LoadWarning>,
+        classes: &mut Vec<String>,
+    ) {
+        for (name, value, propertytype) in properties.iter_typed_mut() {
+            let propertytype = match propertytype {
+                Some(t) => t,
+                None => continue,
+            };
+
+            match self.property_type(propertytype) {
+                Some(&PropertyType::Class(ref class)) => {
+                    if let Property::Class {
+                        ref mut properties, ..
+                    } = *value
+                    {
+                        if !classes.contains(&class.name) {
+                            classes.push(class.name.clone());
+                            self.fill_class(class, properties, warnings, classes);
+                            classes.pop();
+                        }
+                    }
+                }
+                Some(&PropertyType::Enum(ref e)) => {
+                    if !e.is_valid(value) {
+                        warnings.push(LoadWarning::InvalidEnumValue {
+                            property: name.to_string(),
+                            propertytype: propertytype.to_string(),
+                        });
+                    }
+                }
+                None => warnings.push(LoadWarning::UnknownPropertyType {
+                    property: name.to_string(),
+                    propertytype: propertytype.to_string(),
+                }),
+            }
+        }
+    }
+
+    fn fill_class(
+        &self,
+        class: &ClassType,
+        properties: &mut Properties,
+        warnings: &mut Vec<LoadWarning>,
+        classes: &mut Vec<String>,
+    ) {
+        merge_defaults(properties, &class.members);
+        self.apply_nested(properties, warnings, classes);
+    }
+}
+
+/// Adds the members of `defaults` that are missing from `properties`. The
+/// defaults of a class member take precedence over the defaults of its own
+/// class, so they are merged into class members that are present.
+pub(crate) fn merge_defaults(properties: &mut Properties, defaults: &Properties) {
+    for (name, default, propertytype) in defaults.iter_typed() {
+        match properties.get_mut(name) {
+            Some(&mut Property::Class {
+                properties: ref mut members,
+                ..
+            }) => {
+                if let Property::Class {
+                    properties: ref default_members,
+                    ..
+                } = *default
+                {
+                    merge_defaults(members, default_members);
+                }
+                continue;
+            }
+            Some(_) => continue,
+            None => {}
+        }
+
+        properties.insert_typed(name, default.clone(), propertytype.map(String::from));
+    }
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+/// Converts the JSON value of a class member. The members of nested classes
+/// only hold the values that differ from the defaults, and get their types
+/// from the definition of the class.
+fn convert_value(
+    type_: &str,
+    property_type: &str,
+    value: &Value,
+    types: &[RawPropertyType],
+) -> Result<Property, String> {
+    let mismatch = || format!("expected a {} value, found {}", type_, value);
+
+    Ok(match type_ {
+        "string" | "file" => Property::String(value.as_str().ok_or_else(mismatch)?.to_string()),
+        "int" => Property::Int(value.as_i64().ok_or_else(mismatch)?),
+        "float" => Property::Float(value.as_f64().ok_or_else(mismatch)?),
+        "bool" => Property::Bool(value.as_bool().ok_or_else(mismatch)?),
+        "color" => Property::Color(color_value(value.as_str().ok_or_else(mismatch)?)?),
+        "object" => Property::Object(value.as_u64().ok_or_else(mismatch)? as u32),
+        "class" => {
+            let class = types
+                .iter()
+                .find(|t| t.type_ == "class" && t.name == property_type)
+                .ok_or_else(|| format!("unknown class '{}'", property_type))?;
+
+            let mut properties = Properties::new();
+
+            if let Some(values) = value.as_object() {
+                for (name, value) in values {
+                    let member = class
+                        .members
+                        .iter()
+                        .find(|m| &m.name == name)
+                        .ok_or_else(|| format!("'{}' is not a member of '{}'", name, class.name))?;
+
+                    properties.insert_typed(
+                        name.clone(),
+                        convert_value(&member.type_, &member.property_type, value, types)?,
+                        non_empty(&member.property_type),
+                    );
+                }
+            }
+
+            Property::Class {
+                propertytype: property_type.to_string(),
+                properties: properties,
+            }
+        }
+        t => return Err(format!("unexpected property type: '{}'", t)),
+    })
+}
+
+impl Map {
+    /// Fills in class members and checks enum values in all properties of
+    /// the map, see `Project`. Problems are added to `warnings`.
+    pub(crate) fn apply_project(&mut self, project: &Project) {
+        let mut warnings = Vec::new();
+
+        {
+            let mut apply = |properties: &mut Option<Properties>| {
+                if let Some(ref mut properties) = *properties {
+                    project.apply(properties, &mut warnings);
+                }
+            };
+
+            apply(&mut self.properties);
+
+            for tileset in &mut self.tilesets {
+                for tile in tileset.tiles.values_mut() {
+                    apply(&mut tile.properties);
+                    if let Some(ref mut group) = tile.objectgroup {
+                        apply_to_group(group, &mut apply);
+                    }
+                }
+                for wangset in &mut tileset.wangsets {
+                    apply(&mut wangset.properties);
+                    for color in &mut wangset.colors {
+                        apply(&mut color.properties);
+                    }
+                }
+                for terrain in &mut tileset.terraintypes {
+                    apply(&mut terrain.properties);
+                }
+            }
+
+            apply_to_layers(&mut self.layers, &mut apply);
+        }
+
+        self.warnings.extend(warnings);
+    }
+}
+
+fn apply_to_layers<F: FnMut(&mut Option<Properties>)>(layers: &mut [MapLayer], apply: &mut F) {
+    for layer in layers {
+        match *layer {
+            MapLayer::Layer(ref mut l) => apply(&mut l.common.properties),
+            MapLayer::ObjectGroup(ref mut o) => apply_to_group(o, apply),
+            MapLayer::ImageLayer(ref mut i) => apply(&mut i.common.properties),
+            MapLayer::Group(ref mut g) => {
+                apply(&mut g.common.properties);
+                apply_to_layers(&mut g.layers, apply);
+            }
+        }
+    }
+}
+
+fn apply_to_group<F: FnMut(&mut Option<Properties>)>(group: &mut Objectgroup, apply: &mut F) {
+    apply(&mut group.common.properties);
+    for object in &mut group.objects {
+        apply(&mut object.properties);
+    }
+}
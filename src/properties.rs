@@ -1,68 +1,481 @@
-use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::slice;
+
 use serde::de;
 
-use super::Property;
+use color::parse_color;
+use {Color, Property};
+
+/// The custom properties of a map, layer, tileset, tile or object, in the
+/// order they were written in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Properties {
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    name: String,
+    value: Property,
+    /// The custom type of class and enum properties.
+    propertytype: Option<String>,
+}
+
+/// An error when reading a property with one of the typed getters of
+/// `Properties`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PropertyError {
+    /// There is no property with the name.
+    Missing { name: String },
+    /// The property has a different type than requested.
+    WrongType {
+        name: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The properties could not be deserialized into the requested type, see
+    /// `Properties::deserialize_into`.
+    Deserialize(String),
+}
+
+impl fmt::Display for PropertyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PropertyError::Missing { ref name } => write!(f, "property '{}' is missing", name),
+            PropertyError::WrongType {
+                ref name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "property '{}' should be of type {}, but is of type {}",
+                name, expected, found
+            ),
+            PropertyError::Deserialize(ref message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for PropertyError {}
+
+impl Property {
+    /// The name of the type of the property, as used by tiled.
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Property::String(_) => "string",
+            Property::Bool(_) => "bool",
+            Property::Float(_) => "float",
+            Property::Int(_) => "int",
+            Property::Color(_) => "color",
+            Property::Object(_) => "object",
+            Property::Class { .. } => "class",
+        }
+    }
+}
+
+/// Types that property values can be read as, see `Properties::get_or`.
+pub trait FromProperty<'a>: Sized {
+    /// The name of the tiled property type, for errors.
+    const TYPE_NAME: &'static str;
+
+    /// Converts the property, or returns `None` if it has a different type.
+    fn from_property(property: &'a Property) -> Option<Self>;
+}
+
+impl<'a> FromProperty<'a> for bool {
+    const TYPE_NAME: &'static str = "bool";
+
+    fn from_property(property: &'a Property) -> Option<bool> {
+        match *property {
+            Property::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromProperty<'a> for i64 {
+    const TYPE_NAME: &'static str = "int";
+
+    fn from_property(property: &'a Property) -> Option<i64> {
+        match *property {
+            Property::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+}
+
+/// Int properties are converted to floats as well.
+impl<'a> FromProperty<'a> for f64 {
+    const TYPE_NAME: &'static str = "float";
+
+    fn from_property(property: &'a Property) -> Option<f64> {
+        match *property {
+            Property::Float(f) => Some(f),
+            Property::Int(i) => Some(i as f64),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromProperty<'a> for &'a str {
+    const TYPE_NAME: &'static str = "string";
+
+    fn from_property(property: &'a Property) -> Option<&'a str> {
+        match *property {
+            Property::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromProperty<'a> for Color {
+    const TYPE_NAME: &'static str = "color";
+
+    fn from_property(property: &'a Property) -> Option<Color> {
+        match *property {
+            Property::Color(c) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl Properties {
+    pub fn new() -> Properties {
+        Properties::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn entry(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.entry(name).map(|e| &e.value)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Property> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.name == name)
+            .map(|e| &mut e.value)
+    }
+
+    /// Returns the name of the custom type of a class or enum property, as
+    /// defined in the project (see `Project`).
+    pub fn property_type(&self, name: &str) -> Option<&str> {
+        self.entry(name)
+            .and_then(|e| e.propertytype.as_ref())
+            .map(|t| &t[..])
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
 
-pub type Properties = HashMap<String, Property>;
+    /// Sets a property and returns its previous value. New properties are
+    /// added at the end, existing ones keep their position.
+    pub fn insert<S: Into<String>>(&mut self, name: S, value: Property) -> Option<Property> {
+        self.insert_typed(name, value, None)
+    }
+
+    /// Like `insert`, for a property of a custom type.
+    pub fn insert_typed<S: Into<String>>(
+        &mut self,
+        name: S,
+        value: Property,
+        propertytype: Option<String>,
+    ) -> Option<Property> {
+        let name = name.into();
+
+        if let Some(existing) = self.entries.iter_mut().find(|e| e.name == name) {
+            existing.propertytype = propertytype;
+            return Some(::std::mem::replace(&mut existing.value, value));
+        }
+
+        self.entries.push(Entry {
+            name: name,
+            value: value,
+            propertytype: propertytype,
+        });
+        None
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Property> {
+        let index = self.entries.iter().position(|e| e.name == name)?;
+        Some(self.entries.remove(index).value)
+    }
+
+    /// Iterates over the names, values and custom types of the properties.
+    pub(crate) fn iter_typed<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (&'a str, &'a Property, Option<&'a str>)> + 'a {
+        self.entries.iter().map(|e| {
+            (
+                &e.name[..],
+                &e.value,
+                e.propertytype.as_ref().map(|t| &t[..]),
+            )
+        })
+    }
+
+    pub(crate) fn iter_typed_mut<'a>(
+        &'a mut self,
+    ) -> impl Iterator<Item = (&'a str, &'a mut Property, Option<&'a str>)> + 'a {
+        self.entries.iter_mut().map(|e| {
+            (
+                &e.name[..],
+                &mut e.value,
+                e.propertytype.as_ref().map(|t| &t[..]),
+            )
+        })
+    }
+
+    /// Iterates over the names and values of the properties, in order.
+    pub fn iter(&self) -> PropertiesIter {
+        PropertiesIter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    fn get_as<'a, T: FromProperty<'a>>(&'a self, name: &str) -> Result<Option<T>, PropertyError> {
+        match self.get(name) {
+            Some(property) => {
+                T::from_property(property)
+                    .map(Some)
+                    .ok_or_else(|| PropertyError::WrongType {
+                        name: name.to_string(),
+                        expected: T::TYPE_NAME,
+                        found: property.type_name(),
+                    })
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get_required<'a, T: FromProperty<'a>>(&'a self, name: &str) -> Result<T, PropertyError> {
+        self.get_as(name)?.ok_or_else(|| PropertyError::Missing {
+            name: name.to_string(),
+        })
+    }
+
+    pub fn get_bool(&self, name: &str) -> Result<bool, PropertyError> {
+        self.get_required(name)
+    }
+
+    pub fn get_int(&self, name: &str) -> Result<i64, PropertyError> {
+        self.get_required(name)
+    }
+
+    /// Reads a float property. Int properties are converted.
+    pub fn get_float(&self, name: &str) -> Result<f64, PropertyError> {
+        self.get_required(name)
+    }
+
+    pub fn get_str(&self, name: &str) -> Result<&str, PropertyError> {
+        self.get_required(name)
+    }
+
+    pub fn get_color(&self, name: &str) -> Result<Color, PropertyError> {
+        self.get_required(name)
+    }
+
+    /// Reads a property of any type supported by the typed getters, or
+    /// returns `default` if it is missing. Properties of another type are
+    /// still an error.
+    pub fn get_or<'a, T: FromProperty<'a>>(
+        &'a self,
+        name: &str,
+        default: T,
+    ) -> Result<T, PropertyError> {
+        self.get_as(name).map(|value| value.unwrap_or(default))
+    }
+}
+
+/// An iterator over the names and values of `Properties`, in order.
+pub struct PropertiesIter<'a> {
+    inner: slice::Iter<'a, Entry>,
+}
+
+impl<'a> Iterator for PropertiesIter<'a> {
+    type Item = (&'a str, &'a Property);
+
+    fn next(&mut self) -> Option<(&'a str, &'a Property)> {
+        self.inner.next().map(|e| (&e.name[..], &e.value))
+    }
+}
+
+impl<'a> IntoIterator for &'a Properties {
+    type Item = (&'a str, &'a Property);
+    type IntoIter = PropertiesIter<'a>;
+
+    fn into_iter(self) -> PropertiesIter<'a> {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawProperty {
+    name: String,
+    #[serde(rename(deserialize = "type"))]
+    #[serde(default)]
+    type_: String,
+    value: Option<String>,
+    #[serde(default)]
+    propertytype: String,
+    #[serde(rename = "$value", default)]
+    content: Option<RawPropertyContent>,
+}
+
+/// The children of a property: the members of class properties, or the text
+/// of multiline string properties, which have no `value` attribute.
+#[derive(Debug)]
+enum RawPropertyContent {
+    Text(String),
+    Members(RawProperties),
+}
+
+impl<'de> de::Deserialize<'de> for RawPropertyContent {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<RawPropertyContent, D::Error> {
+        struct ContentVisitor;
+
+        impl<'de> de::Visitor<'de> for ContentVisitor {
+            type Value = RawPropertyContent;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("text or properties")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<RawPropertyContent, E> {
+                Ok(RawPropertyContent::Text(value.to_string()))
+            }
+
+            fn visit_map<M: de::MapAccess<'de>>(
+                self,
+                mut map: M,
+            ) -> Result<RawPropertyContent, M::Error> {
+                let mut properties = Vec::new();
+
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "property" {
+                        properties.push(map.next_value()?);
+                    } else {
+                        map.next_value::<de::IgnoredAny>()?;
+                    }
+                }
+
+                Ok(RawPropertyContent::Members(RawProperties {
+                    properties: properties,
+                }))
+            }
+        }
+
+        deserializer.deserialize_any(ContentVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawProperties {
+    #[serde(rename(deserialize = "property"))]
+    #[serde(default)]
+    properties: Vec<RawProperty>,
+}
 
 pub fn deserialize_properties<'de, D: de::Deserializer<'de>>(
     deserializer: D,
 ) -> Result<Option<Properties>, D::Error> {
-    #[derive(Debug, Deserialize)]
-    pub struct RawProperty {
-        pub name: String,
-        #[serde(rename(deserialize = "type"))]
-        #[serde(default)]
-        pub type_: String,
-        pub value: String,
+    let raw_props: RawProperties = de::Deserialize::deserialize(deserializer)?;
+
+    convert_properties(raw_props)
+        .map(Some)
+        .map_err(de::Error::custom)
+}
+
+/// Parses the value of a color property. Tiled writes an empty value for
+/// unset colors, which are transparent.
+pub(crate) fn color_value(value: &str) -> Result<Color, String> {
+    if value.is_empty() {
+        Ok(Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0,
+        })
+    } else {
+        parse_color(value)
     }
+}
 
-    #[derive(Debug, Deserialize)]
-    pub struct RawProperties {
-        #[serde(rename(deserialize = "property"))]
-        properties: Vec<RawProperty>,
+/// Parses the value of an object property, which is empty when no object is
+/// set.
+fn object_value(value: &str) -> Result<u32, String> {
+    if value.is_empty() {
+        Ok(0)
+    } else {
+        value
+            .parse()
+            .map_err(|e| format!("could not parse object id: {}", e))
     }
+}
 
-    let raw_props: RawProperties = de::Deserialize::deserialize(deserializer)?;
+fn convert_properties(raw_props: RawProperties) -> Result<Properties, String> {
     let mut props = Properties::new();
 
     use std::num::ParseFloatError;
     use std::num::ParseIntError;
     use std::str::ParseBoolError;
-    use std::error::Error;
 
     for raw_prop in raw_props.properties {
         if props.contains_key(&raw_prop.name) {
-            return Err(de::Error::custom(format!(
-                "property '{}' was found twice",
-                raw_prop.name
-            )));
+            return Err(format!("property '{}' was found twice", raw_prop.name));
         }
 
+        let (text, members) = match raw_prop.content {
+            Some(RawPropertyContent::Text(text)) => (Some(text), None),
+            Some(RawPropertyContent::Members(members)) => (None, Some(members)),
+            None => (None, None),
+        };
+        let value = raw_prop.value.or(text).unwrap_or_default();
+
         let val = match &raw_prop.type_[..] {
-            "" | "string" => Property::String(raw_prop.value),
-            "float" => Property::Float(raw_prop
-                .value
+            "" | "string" | "file" => Property::String(value),
+            "float" => Property::Float(value
                 .parse()
-                .map_err(|e: ParseFloatError| de::Error::custom(e.description()))?),
-            "bool" => Property::Bool(raw_prop
-                .value
+                .map_err(|e: ParseFloatError| e.description().to_string())?),
+            "bool" => Property::Bool(value
                 .parse()
-                .map_err(|e: ParseBoolError| de::Error::custom(e.description()))?),
-            "int" => Property::Int(raw_prop
-                .value
+                .map_err(|e: ParseBoolError| e.description().to_string())?),
+            "int" => Property::Int(value
                 .parse()
-                .map_err(|e: ParseIntError| de::Error::custom(e.description()))?),
-            s => {
-                return Err(de::Error::custom(format!(
-                    "unexpected property type: '{}'",
-                    s
-                )))
-            }
+                .map_err(|e: ParseIntError| e.description().to_string())?),
+            "color" => Property::Color(color_value(&value)?),
+            "object" => Property::Object(object_value(&value)?),
+            "class" => Property::Class {
+                propertytype: raw_prop.propertytype.clone(),
+                properties: match members {
+                    Some(members) => convert_properties(members)?,
+                    None => Properties::new(),
+                },
+            },
+            s => return Err(format!("unexpected property type: '{}'", s)),
         };
 
-        props.insert(raw_prop.name, val);
+        let propertytype = if raw_prop.propertytype.is_empty() {
+            None
+        } else {
+            Some(raw_prop.propertytype)
+        };
+        props.insert_typed(raw_prop.name, val, propertytype);
     }
 
-    Ok(Some(props))
+    Ok(props)
 }
@@ -0,0 +1,147 @@
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+
+use {Properties, PropertiesIter, Property, PropertyError};
+
+impl de::Error for PropertyError {
+    fn custom<T: fmt::Display>(message: T) -> PropertyError {
+        PropertyError::Deserialize(message.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> PropertyError {
+        PropertyError::Missing {
+            name: field.to_string(),
+        }
+    }
+}
+
+impl PropertyError {
+    /// Adds the name of the property the error happened in to the message.
+    fn in_property(self, name: &str) -> PropertyError {
+        match self {
+            PropertyError::Deserialize(message) => {
+                PropertyError::Deserialize(format!("property '{}': {}", name, message))
+            }
+            e => e,
+        }
+    }
+}
+
+impl Properties {
+    /// Deserializes the properties into a type implementing `Deserialize`,
+    /// usually a struct with fields named like the properties.
+    ///
+    /// Class properties are deserialized like nested structs. Enums can be
+    /// read from string properties holding the name of a variant, or from int
+    /// properties holding its index. Properties that may be missing need to
+    /// be `Option` fields, or have a `#[serde(default)]`.
+    pub fn deserialize_into<'de, T: de::Deserialize<'de>>(&'de self) -> Result<T, PropertyError> {
+        T::deserialize(self)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de Properties {
+    type Error = PropertyError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PropertyError> {
+        visitor.visit_map(PropertiesAccess {
+            iter: self.iter(),
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct
+        map struct enum identifier ignored_any
+    }
+}
+
+/// Visits the properties as a map from their names to their values.
+struct PropertiesAccess<'de> {
+    iter: PropertiesIter<'de>,
+    /// The property whose name was visited last.
+    value: Option<(&'de str, &'de Property)>,
+}
+
+impl<'de> de::MapAccess<'de> for PropertiesAccess<'de> {
+    type Error = PropertyError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, PropertyError>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((name, property)) => {
+                self.value = Some((name, property));
+                seed.deserialize(name.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, PropertyError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (name, property) = self
+            .value
+            .take()
+            .expect("next_value_seed is called after next_key_seed");
+
+        seed.deserialize(property).map_err(|e| e.in_property(name))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de Property {
+    type Error = PropertyError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PropertyError> {
+        match *self {
+            Property::String(ref s) => visitor.visit_borrowed_str(s),
+            Property::Bool(b) => visitor.visit_bool(b),
+            Property::Float(f) => visitor.visit_f64(f),
+            Property::Int(i) => visitor.visit_i64(i),
+            // in the format `Color` is deserialized from
+            Property::Color(c) => {
+                visitor.visit_string(format!("#{:02x}{:02x}{:02x}{:02x}", c.a, c.r, c.g, c.b))
+            }
+            Property::Object(id) => visitor.visit_u32(id),
+            Property::Class { ref properties, .. } => properties.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PropertyError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, PropertyError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, PropertyError> {
+        match *self {
+            Property::String(ref s) => visitor.visit_enum(s.as_str().into_deserializer()),
+            Property::Int(i) if i >= 0 && i <= u32::max_value() as i64 => {
+                visitor.visit_enum((i as u32).into_deserializer())
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use {Map, MapLayer, Object, Objectgroup, Properties, Property};
+
+/// A problem with the contents of a map that was loaded successfully, see
+/// `Map::validate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// An object property refers to an object that is not part of the map.
+    /// `location` describes what the property belongs to, and `property` is
+    /// its name, with the names of the class properties it is nested in.
+    DanglingObjectReference {
+        location: String,
+        property: String,
+        id: u32,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::DanglingObjectReference {
+                ref location,
+                ref property,
+                id,
+            } => write!(
+                f,
+                "property '{}' of {} refers to object {}, which does not exist",
+                property, location, id
+            ),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+impl Map {
+    /// Finds the object with the given id, together with the object group it
+    /// is in. Object groups nested in groups are searched as well.
+    pub fn object_by_id(&self, id: u32) -> Option<(&Objectgroup, &Object)> {
+        self.object_groups()
+            .flat_map(|g| g.objects.iter().map(move |o| (g, o)))
+            .find(|&(_, o)| o.id == id)
+    }
+
+    /// Finds the object an object property refers to, see `object_by_id`.
+    /// Returns `None` for other properties, properties without an object and
+    /// references to objects that do not exist.
+    pub fn resolve_object_property(&self, property: &Property) -> Option<(&Objectgroup, &Object)> {
+        match *property {
+            Property::Object(id) if id != 0 => self.object_by_id(id),
+            _ => None,
+        }
+    }
+
+    /// Checks that the object properties of the map, its layers and its
+    /// objects refer to objects that exist, including the ones nested in
+    /// class properties.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let ids: HashSet<u32> = self
+            .object_groups()
+            .flat_map(|g| g.objects.iter())
+            .map(|o| o.id)
+            .collect();
+        let mut errors = Vec::new();
+
+        {
+            let mut check = |location: String, properties: &Option<Properties>| {
+                if let Some(ref properties) = *properties {
+                    check_references(properties, "", &ids, &mut |property, id| {
+                        errors.push(ValidationError::DanglingObjectReference {
+                            location: location.clone(),
+                            property: property,
+                            id: id,
+                        })
+                    });
+                }
+            };
+
+            check("the map".to_string(), &self.properties);
+
+            for layer in self.all_layers() {
+                let common = layer.common();
+                check(format!("layer '{}'", common.name), &common.properties);
+
+                if let MapLayer::ObjectGroup(ref group) = *layer {
+                    for object in &group.objects {
+                        check(format!("object {}", object.id), &object.properties);
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+/// Calls `dangling` with the name and value of every object property that
+/// refers to an object that is not in `ids`. Names of class members are
+/// prefixed with `prefix`.
+fn check_references<F: FnMut(String, u32)>(
+    properties: &Properties,
+    prefix: &str,
+    ids: &HashSet<u32>,
+    dangling: &mut F,
+) {
+    for (name, property) in properties {
+        match *property {
+            Property::Object(id) if id != 0 && !ids.contains(&id) => {
+                dangling(format!("{}{}", prefix, name), id)
+            }
+            Property::Class { ref properties, .. } => {
+                check_references(properties, &format!("{}{}.", prefix, name), ids, dangling)
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use image::{self, Rgba, RgbaImage};
+
+use debug_draw;
+
+use {
+    Color, Image, ImageLayer, Layer, LayerCommon, Map, MapLayer, Object, Objectgroup, Orientation,
+    TileGid,
+};
+
+/// An image used by the map could not be loaded.
+#[derive(Debug)]
+pub struct RenderError {
+    pub path: PathBuf,
+    pub error: image::ImageError,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "could not load image '{}': {}",
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+impl Error for RenderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Draws a map into an RGBA image in software, using the images of its
+/// tilesets and image layers.
+///
+/// Layers are drawn as they appear in tiled with the camera at the origin,
+/// so parallax scrolling factors are not applied. Layers inside of groups are
+/// drawn with the offsets, opacities and tints of their groups combined, and
+/// not at all if a group is hidden. The 120° rotation of tiles
+/// on hexagonal maps is not supported either.
+pub struct Renderer<'a> {
+    pub options: RenderOptions,
+
+    map: &'a Map,
+    images: HashMap<String, RgbaImage>,
+}
+
+/// Options that control what a `Renderer` draws.
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    /// Whether to draw the shapes and names of objects over their object
+    /// groups, in the color of the group.
+    pub draw_objects: bool,
+}
+
+impl<'a> Renderer<'a> {
+    /// Loads all images used by `map`. Their sources are relative to
+    /// `base_dir`, which usually is the directory containing the map file.
+    pub fn new<P: AsRef<Path>>(map: &'a Map, base_dir: P) -> Result<Renderer<'a>, RenderError> {
+        let images = load_images(used_images(map), base_dir.as_ref())?;
+
+        Ok(Renderer {
+            options: RenderOptions::default(),
+            map: map,
+            images: images,
+        })
+    }
+
+    /// Renders the whole map, see `Map::pixel_size`.
+    pub fn render(&self) -> RgbaImage {
+        let (width, height) = self.map.pixel_size();
+        self.render_rect(0, 0, width, height)
+    }
+
+    /// Renders the part of the map inside the given rectangle, in pixels
+    /// relative to the top-left corner of the map.
+    pub fn render_rect(&self, x: i32, y: i32, width: u32, height: u32) -> RgbaImage {
+        let background = self
+            .map
+            .backgroundcolor
+            .as_ref()
+            .map_or([0; 4], |c| [c.r, c.g, c.b, c.a]);
+
+        let mut canvas = Canvas {
+            image: RgbaImage::from_pixel(width, height, Rgba { data: background }),
+            origin: (x as f32, y as f32),
+        };
+
+        self.draw_layers(&mut canvas, &self.map.layers, &Inherited::root());
+
+        canvas.image
+    }
+
+    /// Draws the visible layers in order, descending into groups.
+    fn draw_layers(&self, canvas: &mut Canvas, layers: &[MapLayer], parent: &Inherited) {
+        for layer in layers {
+            if !layer.common().visible {
+                continue;
+            }
+
+            let inherited = parent.child(layer.common());
+
+            match *layer {
+                MapLayer::Layer(ref l) => self.draw_tile_layer(canvas, l, &inherited),
+                MapLayer::ObjectGroup(ref o) => {
+                    self.draw_object_group(canvas, o, &inherited);
+
+                    if self.options.draw_objects {
+                        debug_draw::draw_objects(
+                            canvas,
+                            self.map,
+                            o,
+                            inherited.offset,
+                            inherited.opacity,
+                        );
+                    }
+                }
+                MapLayer::ImageLayer(ref il) => self.draw_image_layer(canvas, il, &inherited),
+                MapLayer::Group(ref g) => self.draw_layers(canvas, &g.layers, &inherited),
+            }
+        }
+    }
+
+    fn draw_tile_layer(&self, canvas: &mut Canvas, layer: &Layer, inherited: &Inherited) {
+        let tint = inherited.tint_factors();
+
+        for (x, y, gid) in layer.tiles_in_render_order(self.map) {
+            let sprite = match self.tile_sprite(gid) {
+                Some(s) => s,
+                None => continue,
+            };
+
+            let (px, py) = self.map.tile_to_pixel(x as i32, y as i32);
+            let (width, height) = sprite.size();
+
+            // tiles are anchored at the bottom-left corner of their cell
+            let left = px + inherited.offset.0 + sprite.offset.0;
+            let bottom = py + self.map.tileheight as f32 + inherited.offset.1 + sprite.offset.1;
+
+            canvas.draw(
+                &sprite,
+                &Placement {
+                    x: left,
+                    y: bottom - height,
+                    width: width,
+                    height: height,
+                    rotation: 0.0,
+                    pivot: (left, bottom),
+                },
+                tint,
+            );
+        }
+    }
+
+    fn draw_object_group(&self, canvas: &mut Canvas, group: &Objectgroup, inherited: &Inherited) {
+        let tint = inherited.tint_factors();
+
+        let mut objects: Vec<&Object> = group.objects.iter().collect();
+        if group.draworder.as_ref().map_or(true, |o| o == "topdown") {
+            objects.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(::std::cmp::Ordering::Equal));
+        }
+
+        for object in objects {
+            let gid = match object.gid {
+                Some(gid) => TileGid(gid),
+                None => continue,
+            };
+            let sprite = match self.tile_sprite(gid) {
+                Some(s) => s,
+                None => continue,
+            };
+
+            let (sprite_width, sprite_height) = sprite.size();
+            let width = object.width.unwrap_or(sprite_width);
+            let height = object.height.unwrap_or(sprite_height);
+
+            let (px, py) = self.map.object_to_pixel(object.x, object.y);
+            let pivot = (
+                px + inherited.offset.0 + sprite.offset.0,
+                py + inherited.offset.1 + sprite.offset.1,
+            );
+
+            // tile objects are anchored at their bottom-left corner, or at
+            // their bottom center on isometric maps
+            let left = match self.map.orientation {
+                Orientation::Isometric => pivot.0 - width / 2.0,
+                _ => pivot.0,
+            };
+
+            canvas.draw(
+                &sprite,
+                &Placement {
+                    x: left,
+                    y: pivot.1 - height,
+                    width: width,
+                    height: height,
+                    rotation: object.rotation.unwrap_or(0.0),
+                    pivot: pivot,
+                },
+                tint,
+            );
+        }
+    }
+
+    fn draw_image_layer(&self, canvas: &mut Canvas, layer: &ImageLayer, inherited: &Inherited) {
+        let image = match self.images.get(&layer.image.source) {
+            Some(i) => i,
+            None => return,
+        };
+
+        let sprite = Sprite {
+            image: image,
+            rect: (0, 0, image.width(), image.height()),
+            offset: (0.0, 0.0),
+            gid: TileGid(0),
+        };
+        let (x, y) = inherited.offset;
+
+        canvas.draw(
+            &sprite,
+            &Placement {
+                x: x,
+                y: y,
+                width: image.width() as f32,
+                height: image.height() as f32,
+                rotation: 0.0,
+                pivot: (x, y),
+            },
+            inherited.tint_factors(),
+        );
+    }
+
+    fn tile_sprite(&self, gid: TileGid) -> Option<Sprite> {
+        let (tileset, local_id) = self.map.tileset_for_gid(gid)?;
+        let image = self.images.get(&tileset.tile_image(local_id)?.source)?;
+        let rect = tileset.tile_rect(local_id)?;
+
+        Some(Sprite {
+            image: image,
+            rect: rect,
+            offset: (tileset.tileoffset.x as f32, tileset.tileoffset.y as f32),
+            gid: gid,
+        })
+    }
+}
+
+/// Loads the given images relative to `base_dir`, applying their
+/// transparent color. Images are keyed by their source.
+pub(crate) fn load_images<'m, I>(
+    images: I,
+    base_dir: &Path,
+) -> Result<HashMap<String, RgbaImage>, RenderError>
+where
+    I: IntoIterator<Item = &'m Image>,
+{
+    let mut loaded = HashMap::new();
+
+    for image in images {
+        if loaded.contains_key(&image.source) {
+            continue;
+        }
+
+        let path = base_dir.join(&image.source);
+        let mut pixels = match image::open(&path) {
+            Ok(i) => i.to_rgba(),
+            Err(e) => {
+                return Err(RenderError {
+                    path: path,
+                    error: e,
+                })
+            }
+        };
+
+        if let Some(ref trans) = image.trans {
+            for pixel in pixels.pixels_mut() {
+                if pixel.data[..3] == [trans.r, trans.g, trans.b] {
+                    pixel.data[3] = 0;
+                }
+            }
+        }
+
+        loaded.insert(image.source.clone(), pixels);
+    }
+
+    Ok(loaded)
+}
+
+fn used_images(map: &Map) -> Vec<&Image> {
+    let mut images = Vec::new();
+
+    for tileset in &map.tilesets {
+        images.extend(tileset.image.iter());
+        images.extend(tileset.tiles.values().filter_map(|t| t.image.as_ref()));
+    }
+
+    for layer in map.all_layers() {
+        if let MapLayer::ImageLayer(ref il) = *layer {
+            images.push(&il.image);
+        }
+    }
+
+    images
+}
+
+/// The offset, opacity and tint of a layer combined with the ones of the
+/// groups it is in, like tiled applies them.
+struct Inherited {
+    offset: (f32, f32),
+    opacity: f32,
+    tint: [f32; 4],
+}
+
+impl Inherited {
+    /// The values outside of all layers, which change nothing.
+    fn root() -> Inherited {
+        Inherited {
+            offset: (0.0, 0.0),
+            opacity: 1.0,
+            tint: [1.0; 4],
+        }
+    }
+
+    /// Combines the values with the ones of a layer inside of this group.
+    fn child(&self, common: &LayerCommon) -> Inherited {
+        let tint = common.tintcolor.as_ref().map_or([1.0; 4], color_factors);
+
+        Inherited {
+            offset: (
+                self.offset.0 + common.offsetx,
+                self.offset.1 + common.offsety,
+            ),
+            opacity: self.opacity * common.opacity,
+            tint: [
+                self.tint[0] * tint[0],
+                self.tint[1] * tint[1],
+                self.tint[2] * tint[2],
+                self.tint[3] * tint[3],
+            ],
+        }
+    }
+
+    /// Returns the factors to multiply the color channels of the layer with.
+    fn tint_factors(&self) -> [f32; 4] {
+        [
+            self.tint[0],
+            self.tint[1],
+            self.tint[2],
+            self.tint[3] * self.opacity,
+        ]
+    }
+}
+
+pub(crate) fn color_factors(color: &Color) -> [f32; 4] {
+    [
+        color.r as f32 / 255.0,
+        color.g as f32 / 255.0,
+        color.b as f32 / 255.0,
+        color.a as f32 / 255.0,
+    ]
+}
+
+/// A rectangle of a loaded image, drawn with the flips of a tile.
+struct Sprite<'i> {
+    image: &'i RgbaImage,
+    rect: (u32, u32, u32, u32),
+    offset: (f32, f32),
+    gid: TileGid,
+}
+
+impl<'i> Sprite<'i> {
+    /// The size after flipping, which swaps the axes for diagonal flips.
+    fn size(&self) -> (f32, f32) {
+        let (_, _, w, h) = self.rect;
+
+        if self.gid.flipped_diagonally() {
+            (h as f32, w as f32)
+        } else {
+            (w as f32, h as f32)
+        }
+    }
+
+    /// Returns the color at the relative position `(u, v)` of the flipped
+    /// sprite, both in `0..1`.
+    fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        let (u, v) = self.gid.unflip(u, v);
+
+        let (x, y, w, h) = self.rect;
+        let sx = x + ((u * w as f32) as u32).min(w - 1);
+        let sy = y + ((v * h as f32) as u32).min(h - 1);
+
+        if sx >= self.image.width() || sy >= self.image.height() {
+            return [0.0; 4];
+        }
+
+        let p = self.image.get_pixel(sx, sy).data;
+        [
+            p[0] as f32 / 255.0,
+            p[1] as f32 / 255.0,
+            p[2] as f32 / 255.0,
+            p[3] as f32 / 255.0,
+        ]
+    }
+}
+
+/// Where to draw a sprite, in map pixels. The rectangle is rotated clockwise
+/// by `rotation` degrees around `pivot`.
+struct Placement {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    rotation: f32,
+    pivot: (f32, f32),
+}
+
+pub(crate) struct Canvas {
+    pub image: RgbaImage,
+    /// The map pixel at the top-left corner of the image.
+    pub origin: (f32, f32),
+}
+
+impl Canvas {
+    fn draw(&mut self, sprite: &Sprite, placement: &Placement, tint: [f32; 4]) {
+        if placement.width <= 0.0
+            || placement.height <= 0.0
+            || sprite.rect.2 == 0
+            || sprite.rect.3 == 0
+        {
+            return;
+        }
+
+        let (sin, cos) = placement.rotation.to_radians().sin_cos();
+        let (pivot_x, pivot_y) = placement.pivot;
+
+        // bounding box of the rotated rectangle on the canvas
+        let corners = [
+            (placement.x, placement.y),
+            (placement.x + placement.width, placement.y),
+            (placement.x, placement.y + placement.height),
+            (
+                placement.x + placement.width,
+                placement.y + placement.height,
+            ),
+        ];
+        let (mut min_x, mut min_y) = (::std::f32::INFINITY, ::std::f32::INFINITY);
+        let (mut max_x, mut max_y) = (::std::f32::NEG_INFINITY, ::std::f32::NEG_INFINITY);
+
+        for &(x, y) in corners.iter() {
+            let (dx, dy) = (x - pivot_x, y - pivot_y);
+            let (rx, ry) = (pivot_x + dx * cos - dy * sin, pivot_y + dx * sin + dy * cos);
+            min_x = min_x.min(rx);
+            min_y = min_y.min(ry);
+            max_x = max_x.max(rx);
+            max_y = max_y.max(ry);
+        }
+
+        let (origin_x, origin_y) = self.origin;
+        let first_x = ((min_x - origin_x).floor().max(0.0)) as u32;
+        let first_y = ((min_y - origin_y).floor().max(0.0)) as u32;
+        let last_x = ((max_x - origin_x).ceil().min(self.image.width() as f32)).max(0.0) as u32;
+        let last_y = ((max_y - origin_y).ceil().min(self.image.height() as f32)).max(0.0) as u32;
+
+        for cy in first_y..last_y {
+            for cx in first_x..last_x {
+                // undo the rotation of the pixel center
+                let (dx, dy) = (
+                    cx as f32 + 0.5 + origin_x - pivot_x,
+                    cy as f32 + 0.5 + origin_y - pivot_y,
+                );
+                let x = pivot_x + dx * cos + dy * sin;
+                let y = pivot_y - dx * sin + dy * cos;
+
+                let u = (x - placement.x) / placement.width;
+                let v = (y - placement.y) / placement.height;
+                if u < 0.0 || u >= 1.0 || v < 0.0 || v >= 1.0 {
+                    continue;
+                }
+
+                let color = sprite.sample(u, v);
+                self.blend(
+                    cx,
+                    cy,
+                    [
+                        color[0] * tint[0],
+                        color[1] * tint[1],
+                        color[2] * tint[2],
+                        color[3] * tint[3],
+                    ],
+                );
+            }
+        }
+    }
+
+    /// Draws a color with straight alpha over the pixel at `(x, y)`.
+    pub fn blend(&mut self, x: u32, y: u32, color: [f32; 4]) {
+        let alpha = color[3];
+        if alpha <= 0.0 {
+            return;
+        }
+
+        let pixel = self.image.get_pixel_mut(x, y);
+        let dst_alpha = pixel.data[3] as f32 / 255.0;
+        let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+
+        for i in 0..3 {
+            let dst = pixel.data[i] as f32 / 255.0;
+            let out = (color[i] * alpha + dst * dst_alpha * (1.0 - alpha)) / out_alpha;
+            pixel.data[i] = (out * 255.0).round() as u8;
+        }
+        pixel.data[3] = (out_alpha * 255.0).round() as u8;
+    }
+}
@@ -0,0 +1,35 @@
+use std::cmp::Ordering;
+
+/// An entry of the open set of an A* search over nodes of type `N`, ordered
+/// so a `BinaryHeap` pops the lowest estimate. Ties are broken by the node,
+/// which keeps searches deterministic.
+pub(crate) struct Candidate<N> {
+    /// The cost so far plus the estimated cost to the goal.
+    pub estimate: f32,
+    pub cost: f32,
+    pub node: N,
+}
+
+impl<N: Ord> PartialEq for Candidate<N> {
+    fn eq(&self, other: &Candidate<N>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N: Ord> Eq for Candidate<N> {}
+
+impl<N: Ord> PartialOrd for Candidate<N> {
+    fn partial_cmp(&self, other: &Candidate<N>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N: Ord> Ord for Candidate<N> {
+    fn cmp(&self, other: &Candidate<N>) -> Ordering {
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
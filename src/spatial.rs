@@ -0,0 +1,379 @@
+use std::collections::{HashMap, HashSet};
+
+use geometry::{cross, polygon_contains, sub, Point, ELLIPSE_SEGMENTS};
+use {Map, MapLayer, Object};
+
+/// Where a ray cast with `SpatialIndex::ray_cast` first hits an object.
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit<'a> {
+    pub object: &'a Object,
+    /// Distance from the origin of the ray, `0` if it starts inside of the
+    /// object.
+    pub distance: f32,
+    pub point: Point,
+}
+
+#[derive(Debug)]
+struct Entry<'a> {
+    object: &'a Object,
+    /// Offset of the layers of the object, which is added to its shape.
+    offset: Point,
+    vertices: Vec<Point>,
+    closed: bool,
+    /// `(min_x, min_y, max_x, max_y)`
+    bounds: (f32, f32, f32, f32),
+}
+
+/// A uniform grid over objects, for finding the objects at a point, in a
+/// rectangle or along a ray without checking each of them.
+///
+/// The shapes of the objects are used, with their rotation applied (see
+/// `Object::world_vertices`), moved by the offsets of their layers.
+/// Positions are in map pixels. Ellipses are approximated with polygons,
+/// except when querying points.
+#[derive(Debug)]
+pub struct SpatialIndex<'a> {
+    map: &'a Map,
+    cell_size: f32,
+    entries: Vec<Entry<'a>>,
+    /// Indices of the entries whose bounds overlap each cell.
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// `(min_x, min_y, max_x, max_y)` of the cells in use.
+    cell_bounds: (i32, i32, i32, i32),
+}
+
+impl Map {
+    /// Builds a spatial index over the objects of all object groups,
+    /// including the ones nested in groups, with cells of the given size (see
+    /// `SpatialIndex::new`). The objects are moved by the offsets of their
+    /// object groups and of the groups those are in.
+    pub fn spatial_index(&self, cell_size: f32) -> SpatialIndex {
+        let mut objects = Vec::new();
+        collect_objects(&self.layers, (0.0, 0.0), &mut objects);
+
+        SpatialIndex::new(self, objects, cell_size)
+    }
+}
+
+/// Collects the objects of the object groups in `layers` and in the groups
+/// nested in them, together with the offsets of their layers added up.
+fn collect_objects<'a>(
+    layers: &'a [MapLayer],
+    offset: Point,
+    objects: &mut Vec<(&'a Object, Point)>,
+) {
+    for layer in layers {
+        let common = layer.common();
+        let offset = (offset.0 + common.offsetx, offset.1 + common.offsety);
+
+        match *layer {
+            MapLayer::ObjectGroup(ref o) => {
+                objects.extend(o.objects.iter().map(|object| (object, offset)));
+            }
+            MapLayer::Group(ref g) => collect_objects(&g.layers, offset, objects),
+            MapLayer::Layer(_) | MapLayer::ImageLayer(_) => {}
+        }
+    }
+}
+
+impl<'a> SpatialIndex<'a> {
+    /// Builds an index over the given objects of `map`, each with the offset
+    /// of its layer in pixels (see `LayerCommon::offsetx`). The cell size
+    /// should be around the size of typical objects and queries, e.g. a few
+    /// tiles.
+    ///
+    /// The index refers to the objects, so it has to be built again when
+    /// they are changed.
+    pub fn new<I>(map: &'a Map, objects: I, cell_size: f32) -> SpatialIndex<'a>
+    where
+        I: IntoIterator<Item = (&'a Object, Point)>,
+    {
+        assert!(cell_size > 0.0, "cell size must be positive");
+
+        let mut index = SpatialIndex {
+            map: map,
+            cell_size: cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+            cell_bounds: (
+                i32::max_value(),
+                i32::max_value(),
+                i32::min_value(),
+                i32::min_value(),
+            ),
+        };
+
+        for (object, offset) in objects {
+            let vertices = object
+                .world_vertices(map, ELLIPSE_SEGMENTS)
+                .into_iter()
+                .map(|(x, y)| (x + offset.0, y + offset.1))
+                .collect();
+            let (x, y, width, height) = object.world_bounds(map);
+            let (x, y) = (x + offset.0, y + offset.1);
+            let bounds = (x, y, x + width, y + height);
+
+            let i = index.entries.len();
+            let (min_x, min_y) = index.cell((bounds.0, bounds.1));
+            let (max_x, max_y) = index.cell((bounds.2, bounds.3));
+
+            for cy in min_y..max_y + 1 {
+                for cx in min_x..max_x + 1 {
+                    index.cells.entry((cx, cy)).or_insert_with(Vec::new).push(i);
+                }
+            }
+
+            let (x0, y0, x1, y1) = index.cell_bounds;
+            index.cell_bounds = (x0.min(min_x), y0.min(min_y), x1.max(max_x), y1.max(max_y));
+
+            index.entries.push(Entry {
+                object: object,
+                offset: offset,
+                vertices: vertices,
+                closed: object.is_closed(),
+                bounds: bounds,
+            });
+        }
+
+        index
+    }
+
+    fn cell(&self, (x, y): Point) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Indices of the entries whose bounds may overlap the given bounds, in
+    /// the order the objects were added.
+    fn candidates(&self, (min_x, min_y, max_x, max_y): (f32, f32, f32, f32)) -> Vec<usize> {
+        let (cx0, cy0) = self.cell((min_x, min_y));
+        let (cx1, cy1) = self.cell((max_x, max_y));
+        let (bx0, by0, bx1, by1) = self.cell_bounds;
+
+        let mut found = HashSet::new();
+
+        for cy in cy0.max(by0)..cy1.min(by1) + 1 {
+            for cx in cx0.max(bx0)..cx1.min(bx1) + 1 {
+                if let Some(entries) = self.cells.get(&(cx, cy)) {
+                    found.extend(entries.iter().cloned());
+                }
+            }
+        }
+
+        let mut found: Vec<usize> = found
+            .into_iter()
+            .filter(|&i| {
+                let (x0, y0, x1, y1) = self.entries[i].bounds;
+                x0 <= max_x && min_x <= x1 && y0 <= max_y && min_y <= y1
+            })
+            .collect();
+        found.sort();
+        found
+    }
+
+    /// Returns the objects whose shape contains the point. Points and
+    /// polylines have no area, so they never contain a point.
+    pub fn query_point(&self, point: Point) -> Vec<&'a Object> {
+        self.candidates((point.0, point.1, point.0, point.1))
+            .into_iter()
+            .map(|i| &self.entries[i])
+            .filter(|e| e.object.contains_point(self.map, sub(point, e.offset)))
+            .map(|e| e.object)
+            .collect()
+    }
+
+    /// Returns the objects whose shape overlaps the rectangle, including
+    /// points and polylines.
+    pub fn query_rect(&self, x: f32, y: f32, width: f32, height: f32) -> Vec<&'a Object> {
+        let corners = [
+            (x, y),
+            (x + width, y),
+            (x + width, y + height),
+            (x, y + height),
+        ];
+
+        self.candidates((x, y, x + width, y + height))
+            .into_iter()
+            .map(|i| &self.entries[i])
+            .filter(|e| e.overlaps_rect(&corners))
+            .map(|e| e.object)
+            .collect()
+    }
+
+    /// Finds the first object hit by a ray from `origin` in `direction`,
+    /// within `max_distance` (which can be infinite). Point objects are never
+    /// hit.
+    pub fn ray_cast(
+        &self,
+        origin: Point,
+        direction: Point,
+        max_distance: f32,
+    ) -> Option<RayHit<'a>> {
+        let length = (direction.0 * direction.0 + direction.1 * direction.1).sqrt();
+        if length == 0.0 || self.entries.is_empty() {
+            return None;
+        }
+        let direction = (direction.0 / length, direction.1 / length);
+
+        // walk through the cells along the ray, until a hit is closer than
+        // the next cell
+        let (mut cx, mut cy) = self.cell(origin);
+        let (step_x, mut next_x, delta_x) = self.ray_steps(cx, origin.0, direction.0);
+        let (step_y, mut next_y, delta_y) = self.ray_steps(cy, origin.1, direction.1);
+        let (bx0, by0, bx1, by1) = self.cell_bounds;
+
+        let mut tested = HashSet::new();
+        let mut best: Option<RayHit<'a>> = None;
+
+        loop {
+            if let Some(entries) = self.cells.get(&(cx, cy)) {
+                for &i in entries {
+                    if !tested.insert(i) {
+                        continue;
+                    }
+
+                    let entry = &self.entries[i];
+                    if let Some((distance, point)) = entry.ray_hit(origin, direction) {
+                        if distance <= max_distance && best.map_or(true, |b| distance < b.distance)
+                        {
+                            best = Some(RayHit {
+                                object: entry.object,
+                                distance: distance,
+                                point: point,
+                            });
+                        }
+                    }
+                }
+            }
+
+            let exit = next_x.min(next_y);
+            if best.map_or(false, |b| b.distance <= exit) || exit > max_distance {
+                break;
+            }
+
+            if next_x < next_y {
+                cx += step_x;
+                next_x += delta_x;
+            } else {
+                cy += step_y;
+                next_y += delta_y;
+            }
+
+            let leaving = (step_x > 0 && cx > bx1)
+                || (step_x < 0 && cx < bx0)
+                || (step_y > 0 && cy > by1)
+                || (step_y < 0 && cy < by0);
+            if leaving {
+                break;
+            }
+        }
+
+        best
+    }
+
+    /// For walking along a ray on one axis: the direction of the steps
+    /// between cells, the distance to the first cell border, and the distance
+    /// between borders.
+    fn ray_steps(&self, cell: i32, origin: f32, direction: f32) -> (i32, f32, f32) {
+        if direction > 0.0 {
+            let border = (cell + 1) as f32 * self.cell_size;
+            (1, (border - origin) / direction, self.cell_size / direction)
+        } else if direction < 0.0 {
+            let border = cell as f32 * self.cell_size;
+            (
+                -1,
+                (border - origin) / direction,
+                -self.cell_size / direction,
+            )
+        } else {
+            (0, ::std::f32::INFINITY, ::std::f32::INFINITY)
+        }
+    }
+}
+
+impl<'a> Entry<'a> {
+    /// The edges of the shape. Closed shapes include the edge back to the
+    /// first vertex.
+    fn edges(&self) -> Vec<(Point, Point)> {
+        let n = self.vertices.len();
+        let count = if self.closed { n } else { n.saturating_sub(1) };
+
+        (0..count)
+            .map(|i| (self.vertices[i], self.vertices[(i + 1) % n]))
+            .collect()
+    }
+
+    fn overlaps_rect(&self, corners: &[Point; 4]) -> bool {
+        let (min, max) = (corners[0], corners[2]);
+        let inside = |(x, y): Point| min.0 <= x && x <= max.0 && min.1 <= y && y <= max.1;
+
+        if self.vertices.iter().any(|&v| inside(v)) {
+            return true;
+        }
+
+        // the rectangle can be entirely inside of the shape
+        if self.closed && polygon_contains(&self.vertices, corners[0]) {
+            return true;
+        }
+
+        self.edges().into_iter().any(|(a, b)| {
+            (0..4).any(|i| segments_intersect(a, b, corners[i], corners[(i + 1) % 4]))
+        })
+    }
+
+    fn ray_hit(&self, origin: Point, direction: Point) -> Option<(f32, Point)> {
+        if self.closed && polygon_contains(&self.vertices, origin) {
+            return Some((0.0, origin));
+        }
+
+        self.edges()
+            .into_iter()
+            .filter_map(|(a, b)| ray_segment(origin, direction, a, b))
+            .fold(None, |best: Option<f32>, t| {
+                Some(best.map_or(t, |b| b.min(t)))
+            })
+            .map(|t| (t, (origin.0 + direction.0 * t, origin.1 + direction.1 * t)))
+    }
+}
+
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (ab, cd) = (sub(b, a), sub(d, c));
+    let denominator = cross(ab, cd);
+
+    if denominator == 0.0 {
+        // parallel segments only touch if they are collinear and overlap
+        if cross(ab, sub(c, a)) != 0.0 {
+            return false;
+        }
+        let project = |p: Point| if ab.0.abs() >= ab.1.abs() { p.0 } else { p.1 };
+        let (a, b) = (project(a).min(project(b)), project(a).max(project(b)));
+        let (c, d) = (project(c).min(project(d)), project(c).max(project(d)));
+        return a <= d && c <= b;
+    }
+
+    let s = cross(sub(c, a), cd) / denominator;
+    let t = cross(sub(c, a), ab) / denominator;
+    0.0 <= s && s <= 1.0 && 0.0 <= t && t <= 1.0
+}
+
+/// The distance along the ray at which it crosses the segment `a`-`b`.
+/// Segments parallel to the ray are never crossed.
+fn ray_segment(origin: Point, direction: Point, a: Point, b: Point) -> Option<f32> {
+    let edge = sub(b, a);
+    let denominator = cross(direction, edge);
+
+    if denominator == 0.0 {
+        return None;
+    }
+
+    let t = cross(sub(a, origin), edge) / denominator;
+    let s = cross(sub(a, origin), direction) / denominator;
+
+    if t >= 0.0 && 0.0 <= s && s <= 1.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
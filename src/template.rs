@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde_xml_rs;
+
+use project::merge_defaults;
+use warnings;
+use {LoadWarning, Map, Object, Properties, Tile, TileGid, XmlError};
+
+/// An object template (`.tx` file). Objects that are instances of it only
+/// store the attributes and properties that differ from the template.
+#[derive(Debug, Deserialize)]
+pub struct Template {
+    /// The tileset of tile templates, with the first gid the object's `gid`
+    /// is relative to.
+    pub tileset: Option<TemplateTileset>,
+    pub object: Object,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateTileset {
+    pub firstgid: u32,
+    pub source: String,
+}
+
+impl Template {
+    pub fn load_from_str(template_str: &str) -> Result<Template, XmlError> {
+        serde_xml_rs::from_str(template_str)
+    }
+
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Template, XmlError> {
+        let mut file = File::open(path)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+
+        Template::load_from_str(&content)
+    }
+}
+
+impl Map {
+    /// Loads the templates used by objects of the map that are not loaded
+    /// yet, with their paths relative to `base_dir`. This is done by
+    /// `load_from_path`, maps loaded from strings need to call it themselves.
+    ///
+    /// The properties of the templates are checked against the project the
+    /// map was loaded with, like the properties of the map. Templates that
+    /// can not be loaded add a `LoadWarning::TemplateNotLoaded`, and their
+    /// objects are left without template data.
+    pub fn load_templates<P: AsRef<Path>>(&mut self, base_dir: P) {
+        let mut paths = Vec::new();
+        for object in self.object_groups().flat_map(|o| o.objects.iter()) {
+            if let Some(ref path) = object.template {
+                if !self.templates.contains_key(path) && !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+        }
+
+        for path in paths {
+            let (template, warnings) =
+                warnings::collect(|| Template::load_from_path(base_dir.as_ref().join(&path)));
+            self.warnings.extend(warnings);
+
+            let mut template = match template {
+                Ok(t) => t,
+                Err(e) => {
+                    self.warnings.push(LoadWarning::TemplateNotLoaded {
+                        template: path,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            if let (Some(project), Some(properties)) =
+                (self.project.as_ref(), template.object.properties.as_mut())
+            {
+                project.apply(properties, &mut self.warnings);
+            }
+
+            self.templates.insert(path, template);
+        }
+    }
+
+    /// Returns the properties of the object together with the ones it
+    /// inherits, like tiled shows them. A property of the object overrides
+    /// the one of its template, which overrides the one of its tile, which
+    /// overrides the default of its class. In detail, from the highest to the
+    /// lowest precedence:
+    ///
+    /// 1. the properties of the object itself,
+    /// 2. the properties of its template (see `load_templates`),
+    /// 3. the properties of its tile, for tile objects,
+    /// 4. the default values of the members of its class, if the map was
+    ///    loaded with a project that defines the class. The class is taken
+    ///    from the object, its template or its tile, in that order.
+    ///
+    /// Properties are inherited as a whole, except that class properties are
+    /// filled in with the defaults of the class of the object.
+    ///
+    /// The tile of a template is only known if the object stores its `gid`,
+    /// as the tilesets of templates are not loaded.
+    pub fn effective_properties(&self, object: &Object) -> Properties {
+        let template = object
+            .template
+            .as_ref()
+            .and_then(|path| self.templates.get(path))
+            .map(|t| &t.object);
+        let tile = object
+            .gid
+            .and_then(|gid| self.tileset_for_gid(TileGid(gid)))
+            .and_then(|(tileset, local_id)| tileset.tile(local_id));
+
+        let mut properties = object.properties.clone().unwrap_or_default();
+
+        let inherited = template
+            .and_then(|t| t.properties.as_ref())
+            .into_iter()
+            .chain(tile.and_then(|t| t.properties.as_ref()));
+        for inherited in inherited {
+            for (name, value, propertytype) in inherited.iter_typed() {
+                if !properties.contains_key(name) {
+                    properties.insert_typed(name, value.clone(), propertytype.map(String::from));
+                }
+            }
+        }
+
+        let class = object
+            .class_name()
+            .or_else(|| template.and_then(Object::class_name))
+            .or_else(|| tile.and_then(Tile::class_name));
+        let class = match (self.project.as_ref(), class) {
+            (Some(project), Some(class)) => project.class(class),
+            _ => None,
+        };
+        if let Some(class) = class {
+            merge_defaults(&mut properties, &class.members);
+        }
+
+        properties
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use serde::de;
+
+use warnings;
+use {Image, LoadWarning, Map, Objectgroup, Properties, Terrain, TileGid, TileTerrain, WangSet};
+
+#[derive(Debug, Deserialize)]
+pub struct Tile {
+    pub id: u32,
+    /// The class of the tile, called type before tiled 1.9.
+    #[serde(rename(deserialize = "type"))]
+    pub type_: Option<String>,
+    pub class: Option<String>,
+
+    /// Sub-rectangle of the tile's image that should be used instead of the
+    /// whole image. Only used by image-collection tilesets.
+    pub x: Option<u32>,
+    pub y: Option<u32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+
+    #[serde(deserialize_with = "::wang::deserialize_tile_terrain")]
+    #[serde(default)]
+    pub terrain: Option<TileTerrain>,
+    #[serde(default = "default_probability")]
+    pub probability: f32,
+
+    #[serde(deserialize_with = "::properties::deserialize_properties")]
+    #[serde(default)]
+    pub properties: Option<Properties>,
+    pub image: Option<Image>,
+    /// Collision shapes of the tile, relative to its top-left corner.
+    pub objectgroup: Option<Objectgroup>,
+}
+
+impl Tile {
+    /// The class of the tile, from `class` or from `type` for tilesets saved
+    /// before tiled 1.9.
+    pub fn class_name(&self) -> Option<&str> {
+        self.class.as_ref().or(self.type_.as_ref()).map(|c| &c[..])
+    }
+}
+
+fn default_probability() -> f32 {
+    1.0
+}
+
+/// Offset in pixels that is applied when drawing tiles of a tileset.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct TileOffset {
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+}
+
+enum_str!(GridOrientation {
+    Orthogonal("orthogonal"),
+    Isometric("isometric"),
+});
+
+/// Determines how tile overlays for terrain and collision information are
+/// rendered in tiled. Only set for isometric tilesets.
+#[derive(Debug, Deserialize)]
+pub struct Grid {
+    pub orientation: GridOrientation,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct TilesetImpl {
+    firstgid: u32,
+    name: String,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    margin: u32,
+    tilecount: u32,
+    columns: u32,
+
+    #[serde(default)]
+    tileoffset: TileOffset,
+    grid: Option<Grid>,
+
+    #[serde(deserialize_with = "::wang::deserialize_terraintypes")]
+    #[serde(default)]
+    terraintypes: Vec<Terrain>,
+    #[serde(deserialize_with = "::wang::deserialize_wangsets")]
+    #[serde(default)]
+    wangsets: Vec<WangSet>,
+
+    #[serde(rename(deserialize = "tile"), default)]
+    tiles: Vec<Tile>,
+    image: Option<Image>,
+}
+
+/// A `Tileset` either consists of a single image containing all tiles (with
+/// `columns > 0`), or is a collection of images, one per tile (`columns == 0`).
+/// In the latter case tile ids can be sparse, so tiles are looked up by their
+/// local id.
+#[derive(Debug)]
+pub struct Tileset {
+    pub firstgid: u32,
+    pub name: String,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    pub tilecount: u32,
+    pub columns: u32,
+
+    pub tileoffset: TileOffset,
+    pub grid: Option<Grid>,
+
+    pub terraintypes: Vec<Terrain>,
+    pub wangsets: Vec<WangSet>,
+
+    pub tiles: BTreeMap<u32, Tile>,
+    pub image: Option<Image>,
+}
+
+impl<'de> de::Deserialize<'de> for Tileset {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Tileset, D::Error> {
+        let tsi: TilesetImpl = de::Deserialize::deserialize(deserializer)?;
+
+        if !tsi.terraintypes.is_empty() {
+            warnings::warn(LoadWarning::LegacyTerrain {
+                tileset: tsi.name.clone(),
+            });
+        }
+
+        let mut tiles = BTreeMap::new();
+
+        for tile in tsi.tiles {
+            if tiles.contains_key(&tile.id) {
+                return Err(de::Error::custom(format!(
+                    "tile '{}' was found twice",
+                    tile.id
+                )));
+            }
+
+            tiles.insert(tile.id, tile);
+        }
+
+        Ok(Tileset {
+            firstgid: tsi.firstgid,
+            name: tsi.name,
+            tilewidth: tsi.tilewidth,
+            tileheight: tsi.tileheight,
+            spacing: tsi.spacing,
+            margin: tsi.margin,
+            tilecount: tsi.tilecount,
+            columns: tsi.columns,
+
+            tileoffset: tsi.tileoffset,
+            grid: tsi.grid,
+
+            terraintypes: tsi.terraintypes,
+            wangsets: tsi.wangsets,
+
+            tiles: tiles,
+            image: tsi.image,
+        })
+    }
+}
+
+impl Tileset {
+    /// Whether this tileset is a collection of images instead of a single one.
+    pub fn is_image_collection(&self) -> bool {
+        self.columns == 0
+    }
+
+    /// Returns the tile with the given local id, if it has any attributes.
+    pub fn tile(&self, local_id: u32) -> Option<&Tile> {
+        self.tiles.get(&local_id)
+    }
+
+    /// Returns the image the tile with the given local id is cut from.
+    pub fn tile_image(&self, local_id: u32) -> Option<&Image> {
+        if self.is_image_collection() {
+            self.tile(local_id).and_then(|t| t.image.as_ref())
+        } else if local_id < self.tilecount {
+            self.image.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the size `(width, height)` of the tile with the given local id.
+    /// For image-collection tilesets, this can differ between tiles.
+    pub fn tile_size(&self, local_id: u32) -> Option<(u32, u32)> {
+        self.tile_rect(local_id).map(|(_, _, w, h)| (w, h))
+    }
+
+    /// Returns the source rectangle `(x, y, width, height)` of the tile with
+    /// the given local id inside its image (see `tile_image`).
+    ///
+    /// For single-image tilesets, `margin` and `spacing` are taken into
+    /// account. For image collections, the tile's sub-rectangle is used, which
+    /// defaults to its whole image.
+    ///
+    /// Returns `None` if there is no tile with this id.
+    pub fn tile_rect(&self, local_id: u32) -> Option<(u32, u32, u32, u32)> {
+        if self.is_image_collection() {
+            let tile = self.tile(local_id)?;
+            let image = tile.image.as_ref()?;
+
+            return Some((
+                tile.x.unwrap_or(0),
+                tile.y.unwrap_or(0),
+                tile.width.unwrap_or(image.width),
+                tile.height.unwrap_or(image.height),
+            ));
+        }
+
+        if local_id >= self.tilecount {
+            return None;
+        }
+
+        let column = local_id % self.columns;
+        let row = local_id / self.columns;
+
+        Some((
+            self.margin + column * (self.tilewidth + self.spacing),
+            self.margin + row * (self.tileheight + self.spacing),
+            self.tilewidth,
+            self.tileheight,
+        ))
+    }
+
+    /// Returns the local id of the first tile whose corners have the given
+    /// terrains (see `Tile::terrain`).
+    pub fn find_terrain_tile(&self, terrain: &TileTerrain) -> Option<u32> {
+        self.tiles
+            .values()
+            .find(|t| t.terrain.as_ref() == Some(terrain))
+            .map(|t| t.id)
+    }
+}
+
+impl Map {
+    /// Returns the tileset a global tile id belongs to, together with the
+    /// local id of the tile in that tileset. Flags of the gid are ignored.
+    pub fn tileset_for_gid(&self, gid: TileGid) -> Option<(&Tileset, u32)> {
+        let gid = gid.gid();
+
+        if gid == 0 {
+            return None;
+        }
+
+        self.tilesets
+            .iter()
+            .filter(|t| t.firstgid <= gid)
+            .max_by_key(|t| t.firstgid)
+            .map(|t| (t, gid - t.firstgid))
+    }
+}
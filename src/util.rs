@@ -48,3 +48,18 @@ macro_rules! enum_str {
         }
     }
 }
+
+/// Deserializes a boolean attribute, which tiled stores as `0` or `1`.
+pub fn deserialize_bool<'de, D: ::serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<bool, D::Error> {
+    use serde::de::Error;
+
+    let bool_str: String = ::serde::Deserialize::deserialize(deserializer)?;
+
+    match &bool_str[..] {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        s => Err(D::Error::custom(format!("expected a boolean, got '{}'", s))),
+    }
+}
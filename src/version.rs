@@ -0,0 +1,103 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de;
+
+use Project;
+
+/// A version number as used for the `version` and `tiledversion` attributes of
+/// a map. Missing components are treated as `0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub fn new(major: u32, minor: u32, patch: u32) -> Version {
+        Version {
+            major: major,
+            minor: minor,
+            patch: patch,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(version_str: &str) -> Result<Version, String> {
+        // ignore suffixes like in "1.4.0-beta" or "1.3.3-52-g1234abc"
+        let numbers = version_str.split('-').next().unwrap_or("");
+
+        let mut components = [0; 3];
+        let mut count = 0;
+
+        for (component, part) in components.iter_mut().zip(numbers.split('.')) {
+            *component = part
+                .trim()
+                .parse()
+                .map_err(|e| format!("could not parse version '{}': {}", version_str, e))?;
+            count += 1;
+        }
+
+        if count == 0 || numbers.split('.').count() > 3 {
+            return Err(format!("could not parse version '{}'", version_str));
+        }
+
+        Ok(Version::new(components[0], components[1], components[2]))
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Version {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Version, D::Error> {
+        let version_str: String = de::Deserialize::deserialize(deserializer)?;
+        version_str.parse().map_err(de::Error::custom)
+    }
+}
+
+/// What to do with maps saved by a version of tiled older than
+/// `LoadOptions::minimum_version`, or by an unknown version if
+/// `LoadOptions::require_tiledversion` is set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Fail to load the map.
+    Refuse,
+    /// Load the map as well as possible, and add a `LoadWarning::OldVersion`
+    /// or `LoadWarning::MissingTiledVersion`.
+    Warn,
+}
+
+/// Options that control how maps are loaded.
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    /// The oldest version of tiled whose maps are supported. This is compared
+    /// to the `tiledversion` of a map.
+    pub minimum_version: Version,
+    pub old_versions: VersionPolicy,
+    /// Whether maps without a `tiledversion`, which were saved before tiled
+    /// 1.0.1 or by other tools, are handled by `old_versions` as well. By
+    /// default, their version is unknown and they are loaded as they are.
+    pub require_tiledversion: bool,
+    /// The project the map belongs to. Its custom property types are used to
+    /// fill in the members of class properties, and to check enum values.
+    pub project: Option<Project>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> LoadOptions {
+        LoadOptions {
+            minimum_version: Version::new(1, 0, 0),
+            old_versions: VersionPolicy::Warn,
+            require_tiledversion: false,
+            project: None,
+        }
+    }
+}
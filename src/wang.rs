@@ -0,0 +1,339 @@
+use serde::de;
+
+use warnings;
+use {Color, LoadWarning, Properties};
+
+enum_str!(WangSetType {
+    Corner("corner"),
+    Edge("edge"),
+    Mixed("mixed"),
+});
+
+/// The colors of the edges and corners of a tile, as indices into the colors
+/// of its `WangSet`. Index `0` means "no color".
+///
+/// The order is: top, top-right, right, bottom-right, bottom, bottom-left,
+/// left, top-left. Edges have even indices, corners odd ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WangId(pub [u8; 8]);
+
+impl WangId {
+    pub const TOP: usize = 0;
+    pub const TOP_RIGHT: usize = 1;
+    pub const RIGHT: usize = 2;
+    pub const BOTTOM_RIGHT: usize = 3;
+    pub const BOTTOM: usize = 4;
+    pub const BOTTOM_LEFT: usize = 5;
+    pub const LEFT: usize = 6;
+    pub const TOP_LEFT: usize = 7;
+
+    /// Creates a `WangId` where only the corners are colored, given in the
+    /// order top-right, bottom-right, bottom-left, top-left.
+    pub fn from_corners(corners: [u8; 4]) -> WangId {
+        let mut id = WangId::default();
+        for (i, &c) in corners.iter().enumerate() {
+            id.0[2 * i + 1] = c;
+        }
+        id
+    }
+
+    /// Creates a `WangId` where only the edges are colored, given in the order
+    /// top, right, bottom, left.
+    pub fn from_edges(edges: [u8; 4]) -> WangId {
+        let mut id = WangId::default();
+        for (i, &e) in edges.iter().enumerate() {
+            id.0[2 * i] = e;
+        }
+        id
+    }
+
+    /// The corner colors in the order top-right, bottom-right, bottom-left,
+    /// top-left.
+    pub fn corners(&self) -> [u8; 4] {
+        [self.0[1], self.0[3], self.0[5], self.0[7]]
+    }
+
+    /// The edge colors in the order top, right, bottom, left.
+    pub fn edges(&self) -> [u8; 4] {
+        [self.0[0], self.0[2], self.0[4], self.0[6]]
+    }
+
+    /// Whether the positions that are relevant for the given type of wang set
+    /// have the same colors in both ids.
+    pub fn matches(&self, other: &WangId, set_type: WangSetType) -> bool {
+        (0..8)
+            .filter(|&i| is_relevant(i, set_type))
+            .all(|i| self.0[i] == other.0[i])
+    }
+
+    /// Returns a copy of this id where all positions that are not relevant for
+    /// the given type of wang set are cleared.
+    pub fn masked(&self, set_type: WangSetType) -> WangId {
+        let mut masked = WangId::default();
+        for i in (0..8).filter(|&i| is_relevant(i, set_type)) {
+            masked.0[i] = self.0[i];
+        }
+        masked
+    }
+}
+
+fn is_relevant(index: usize, set_type: WangSetType) -> bool {
+    match set_type {
+        WangSetType::Corner => index % 2 == 1,
+        WangSetType::Edge => index % 2 == 0,
+        WangSetType::Mixed => true,
+    }
+}
+
+impl<'de> de::Deserialize<'de> for WangId {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<WangId, D::Error> {
+        let id_str: String = de::Deserialize::deserialize(deserializer)?;
+        let mut id = WangId::default();
+
+        if id_str.starts_with("0x") {
+            // legacy format (before tiled 1.5): one nibble per position,
+            // starting with the least significant one
+            let raw = u32::from_str_radix(&id_str[2..], 16)
+                .map_err(|e| de::Error::custom(format!("could not parse wangid: {}", e)))?;
+
+            for (i, color) in id.0.iter_mut().enumerate() {
+                *color = ((raw >> (4 * i)) & 0xf) as u8;
+            }
+
+            return Ok(id);
+        }
+
+        let colors = id_str
+            .split(',')
+            .map(|c| {
+                c.trim()
+                    .parse()
+                    .map_err(|e| de::Error::custom(format!("could not parse wangid: {}", e)))
+            })
+            .collect::<Result<Vec<u8>, D::Error>>()?;
+
+        if colors.len() != 8 {
+            return Err(de::Error::custom(format!(
+                "expected 8 colors in wangid, got {}",
+                colors.len()
+            )));
+        }
+
+        id.0.copy_from_slice(&colors);
+
+        Ok(id)
+    }
+}
+
+fn default_probability() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WangColor {
+    pub name: String,
+    pub color: Color,
+    pub tile: i32,
+    #[serde(default = "default_probability")]
+    pub probability: f32,
+
+    #[serde(deserialize_with = "::properties::deserialize_properties")]
+    #[serde(default)]
+    pub properties: Option<Properties>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WangTile {
+    pub tileid: u32,
+    pub wangid: WangId,
+}
+
+#[derive(Debug, Deserialize)]
+struct WangSetImpl {
+    name: String,
+    #[serde(rename(deserialize = "type"))]
+    type_: Option<WangSetType>,
+    tile: i32,
+
+    #[serde(deserialize_with = "::properties::deserialize_properties")]
+    #[serde(default)]
+    properties: Option<Properties>,
+
+    #[serde(rename(deserialize = "wangcolor"), default)]
+    colors: Vec<WangColor>,
+    #[serde(rename(deserialize = "wangedgecolor"), default)]
+    edge_colors: Vec<WangColor>,
+    #[serde(rename(deserialize = "wangcornercolor"), default)]
+    corner_colors: Vec<WangColor>,
+
+    #[serde(rename(deserialize = "wangtile"), default)]
+    tiles: Vec<WangTile>,
+}
+
+#[derive(Debug)]
+pub struct WangSet {
+    pub name: String,
+    pub type_: WangSetType,
+    pub tile: i32,
+
+    pub properties: Option<Properties>,
+
+    /// The colors of this set. Color `i` is referred to by index `i + 1` in
+    /// `WangId`s.
+    pub colors: Vec<WangColor>,
+    pub tiles: Vec<WangTile>,
+}
+
+impl<'de> de::Deserialize<'de> for WangSet {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<WangSet, D::Error> {
+        let wsi: WangSetImpl = de::Deserialize::deserialize(deserializer)?;
+
+        // sets of the current format can be without colors while they are
+        // being set up, but always have a type
+        let legacy = !wsi.edge_colors.is_empty()
+            || !wsi.corner_colors.is_empty()
+            || (wsi.type_.is_none() && wsi.colors.is_empty());
+
+        if !legacy {
+            let type_ = wsi.type_
+                .ok_or_else(|| de::Error::custom("wangset without type"))?;
+
+            return Ok(WangSet {
+                name: wsi.name,
+                type_: type_,
+                tile: wsi.tile,
+                properties: wsi.properties,
+                colors: wsi.colors,
+                tiles: wsi.tiles,
+            });
+        }
+
+        // legacy format (before tiled 1.5) with separate edge and corner
+        // colors: merge them like tiled does, edge colors first
+        warnings::warn(LoadWarning::LegacyWangSet {
+            wangset: wsi.name.clone(),
+        });
+
+        let type_ = match (wsi.edge_colors.is_empty(), wsi.corner_colors.is_empty()) {
+            (false, true) => WangSetType::Edge,
+            (true, false) => WangSetType::Corner,
+            _ => WangSetType::Mixed,
+        };
+
+        let corner_offset = wsi.edge_colors.len() as u8;
+        let mut tiles = wsi.tiles;
+
+        for tile in &mut tiles {
+            for (i, color) in tile.wangid.0.iter_mut().enumerate() {
+                if i % 2 == 1 && *color != 0 {
+                    *color += corner_offset;
+                }
+            }
+        }
+
+        let mut colors = wsi.edge_colors;
+        colors.extend(wsi.corner_colors);
+
+        Ok(WangSet {
+            name: wsi.name,
+            type_: type_,
+            tile: wsi.tile,
+            properties: wsi.properties,
+            colors: colors,
+            tiles: tiles,
+        })
+    }
+}
+
+impl WangSet {
+    /// Returns the color referred to by the given index of a `WangId`.
+    pub fn color(&self, index: u8) -> Option<&WangColor> {
+        if index == 0 {
+            None
+        } else {
+            self.colors.get(index as usize - 1)
+        }
+    }
+
+    /// Returns all tiles whose `WangId` matches the wanted one. Only corners
+    /// are compared for corner sets, and only edges for edge sets.
+    pub fn matching_tiles<'a>(&'a self, wanted: WangId) -> impl Iterator<Item = &'a WangTile> {
+        let set_type = self.type_;
+        self.tiles
+            .iter()
+            .filter(move |t| t.wangid.matches(&wanted, set_type))
+    }
+
+    /// Returns the first tile whose `WangId` matches the wanted one.
+    pub fn find_tile(&self, wanted: WangId) -> Option<&WangTile> {
+        self.matching_tiles(wanted).next()
+    }
+}
+
+pub fn deserialize_wangsets<'de, D: de::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<WangSet>, D::Error> {
+    #[derive(Debug, Deserialize)]
+    struct RawWangSets {
+        #[serde(rename(deserialize = "wangset"), default)]
+        wangsets: Vec<WangSet>,
+    }
+
+    let raw: RawWangSets = de::Deserialize::deserialize(deserializer)?;
+    Ok(raw.wangsets)
+}
+
+/// A terrain type of the legacy terrain system, which was replaced by wang
+/// sets in tiled 1.5.
+#[derive(Debug, Deserialize)]
+pub struct Terrain {
+    pub name: String,
+    pub tile: i32,
+
+    #[serde(deserialize_with = "::properties::deserialize_properties")]
+    #[serde(default)]
+    pub properties: Option<Properties>,
+}
+
+pub fn deserialize_terraintypes<'de, D: de::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Terrain>, D::Error> {
+    #[derive(Debug, Deserialize)]
+    struct RawTerrainTypes {
+        #[serde(rename(deserialize = "terrain"), default)]
+        terrains: Vec<Terrain>,
+    }
+
+    let raw: RawTerrainTypes = de::Deserialize::deserialize(deserializer)?;
+    Ok(raw.terrains)
+}
+
+/// The terrain of a tile's corners as indices into the tileset's
+/// `terraintypes`, in the order top-left, top-right, bottom-left,
+/// bottom-right. `None` means no terrain.
+pub type TileTerrain = [Option<u32>; 4];
+
+pub fn deserialize_tile_terrain<'de, D: de::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<TileTerrain>, D::Error> {
+    let terrain_str: String = de::Deserialize::deserialize(deserializer)?;
+    let mut terrain = [None; 4];
+
+    let parts: Vec<&str> = terrain_str.split(',').collect();
+    if parts.len() != 4 {
+        return Err(de::Error::custom(format!(
+            "expected 4 corners in terrain, got '{}'",
+            terrain_str
+        )));
+    }
+
+    for (corner, part) in terrain.iter_mut().zip(parts) {
+        if !part.is_empty() {
+            *corner = Some(part.parse().map_err(|e| {
+                de::Error::custom(format!("could not parse terrain: {}", e))
+            })?);
+        }
+    }
+
+    Ok(Some(terrain))
+}
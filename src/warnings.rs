@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::fmt;
+
+use Version;
+
+/// A non-fatal problem that was encountered while loading a map.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadWarning {
+    /// An element that is not supported was skipped.
+    UnknownElement { name: String },
+    /// An attribute that is deprecated by tiled and ignored was found.
+    DeprecatedAttribute { element: String, attribute: String },
+    /// A tileset uses terrains, which were replaced by wang sets in tiled 1.5.
+    LegacyTerrain { tileset: String },
+    /// A wang set uses separate edge and corner colors, which were merged in
+    /// tiled 1.5.
+    LegacyWangSet { wangset: String },
+    /// The map was saved by a version of tiled older than the minimum
+    /// supported one.
+    OldVersion { version: Version, minimum: Version },
+    /// The map does not store the version of tiled that saved it, so it can
+    /// not be checked against the minimum supported one. Only added if
+    /// `LoadOptions::require_tiledversion` is set.
+    MissingTiledVersion,
+    /// The template of an object could not be loaded, see
+    /// `Map::load_templates`.
+    TemplateNotLoaded { template: String, error: String },
+    /// A property has a custom type that is not defined in the project.
+    UnknownPropertyType {
+        property: String,
+        propertytype: String,
+    },
+    /// An enum property has a value that its enum does not define.
+    InvalidEnumValue {
+        property: String,
+        propertytype: String,
+    },
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadWarning::UnknownElement { ref name } => {
+                write!(f, "skipped unknown element <{}>", name)
+            }
+            LoadWarning::DeprecatedAttribute {
+                ref element,
+                ref attribute,
+            } => write!(
+                f,
+                "ignored deprecated attribute '{}' of <{}>",
+                attribute, element
+            ),
+            LoadWarning::LegacyTerrain { ref tileset } => {
+                write!(f, "tileset '{}' uses legacy terrains", tileset)
+            }
+            LoadWarning::LegacyWangSet { ref wangset } => {
+                write!(f, "wang set '{}' uses the legacy format", wangset)
+            }
+            LoadWarning::OldVersion { version, minimum } => write!(
+                f,
+                "map was saved by tiled {}, but only {} and newer are supported",
+                version, minimum
+            ),
+            LoadWarning::MissingTiledVersion => {
+                f.write_str("map does not say which version of tiled saved it")
+            }
+            LoadWarning::TemplateNotLoaded {
+                ref template,
+                ref error,
+            } => write!(f, "could not load template '{}': {}", template, error),
+            LoadWarning::UnknownPropertyType {
+                ref property,
+                ref propertytype,
+            } => write!(
+                f,
+                "property '{}' has the unknown type '{}'",
+                property, propertytype
+            ),
+            LoadWarning::InvalidEnumValue {
+                ref property,
+                ref propertytype,
+            } => write!(
+                f,
+                "property '{}' has a value that is not part of enum '{}'",
+                property, propertytype
+            ),
+        }
+    }
+}
+
+// Deserializers have no way to pass state along, so warnings are collected
+// here while a map is being loaded. The buffer only exists inside of
+// `collect`, so warnings raised outside of it are dropped right away.
+thread_local! {
+    static WARNINGS: RefCell<Option<Vec<LoadWarning>>> = RefCell::new(None);
+}
+
+pub fn warn(warning: LoadWarning) {
+    WARNINGS.with(|w| {
+        if let Some(ref mut warnings) = *w.borrow_mut() {
+            warnings.push(warning);
+        }
+    });
+}
+
+/// Puts back the buffer of an enclosing `collect` when dropped, even if the
+/// load panics, so no warnings are left behind for the next one.
+struct Scope {
+    outer: Option<Vec<LoadWarning>>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let outer = self.outer.take();
+        WARNINGS.with(|w| *w.borrow_mut() = outer);
+    }
+}
+
+/// Runs `load` and returns the warnings raised while it ran. Calls can be
+/// nested, the warnings of the inner call are not part of the outer one.
+pub fn collect<T, F: FnOnce() -> T>(load: F) -> (T, Vec<LoadWarning>) {
+    let _scope = Scope {
+        outer: WARNINGS.with(|w| w.borrow_mut().replace(Vec::new())),
+    };
+
+    let result = load();
+    let warnings = WARNINGS.with(|w| w.borrow_mut().take().unwrap_or_default());
+
+    (result, warnings)
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "render")]
+
+extern crate tiled_loader;
+
+mod common;
+
+use std::error::Error;
+
+use common::{load_map, tile_layer};
+use tiled_loader::{pack_atlas, AtlasError, AtlasOptions, AtlasRect, Map};
+
+fn load_tiles(csv: &str) -> Map {
+    load_map(
+        r#"orientation="orthogonal" renderorder="right-down" width="4" height="1" tilewidth="32" tileheight="32""#,
+        &format!(
+            r#"<tileset firstgid="1" name="multi" tilewidth="32" tileheight="32" tilecount="16" columns="4">
+  <image source="multi.png" width="128" height="128"/>
+ </tileset>
+ {}"#,
+            tile_layer("Tile Layer 1", 4, 1, csv)
+        ),
+    )
+}
+
+fn options(page_size: u32) -> AtlasOptions {
+    AtlasOptions {
+        page_size: page_size,
+        extrude: 1,
+        padding: 1,
+    }
+}
+
+fn rect(page: usize, x: u32, y: u32) -> AtlasRect {
+    AtlasRect {
+        page: page,
+        x: x,
+        y: y,
+        width: 32,
+        height: 32,
+    }
+}
+
+#[test]
+fn tiles_are_placed_on_shelves() {
+    let map = load_tiles("1,2,3,2");
+
+    // each tile takes 35 pixels with its extruded border and the padding,
+    // so two fit next to each other on a shelf
+    let atlas = pack_atlas(&map, "assets", &options(80)).unwrap();
+
+    assert_eq!(atlas.pages.len(), 1);
+    assert_eq!(atlas.pages[0].dimensions(), (70, 70));
+    assert_eq!(atlas.rects.len(), 3);
+    assert_eq!(atlas.rects[&1], rect(0, 1, 1));
+    assert_eq!(atlas.rects[&2], rect(0, 36, 1));
+    assert_eq!(atlas.rects[&3], rect(0, 1, 36));
+}
+
+#[test]
+fn full_pages_start_new_ones() {
+    let map = load_tiles("1,2,0,0");
+    let atlas = pack_atlas(&map, "assets", &options(40)).unwrap();
+
+    assert_eq!(atlas.pages.len(), 2);
+    assert_eq!(atlas.rects[&1], rect(0, 1, 1));
+    assert_eq!(atlas.rects[&2], rect(1, 1, 1));
+}
+
+#[test]
+fn tile_too_large() {
+    let map = load_tiles("1,0,0,0");
+
+    match pack_atlas(&map, "assets", &options(34)) {
+        Err(AtlasError::TileTooLarge { gid, width, height }) => {
+            assert_eq!((gid, width, height), (1, 32, 32))
+        }
+        other => panic!("expected TileTooLarge, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_image() {
+    let map = load_tiles("1,0,0,0");
+    let error = pack_atlas(&map, "tests", &options(64)).unwrap_err();
+
+    match error {
+        AtlasError::Image(ref e) => assert!(e.path.ends_with("multi.png")),
+        ref e => panic!("expected an image error, got {:?}", e),
+    }
+    assert!(error.source().unwrap().source().is_some());
+}
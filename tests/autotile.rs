@@ -0,0 +1,151 @@
+extern crate tiled_loader;
+
+mod common;
+
+use common::{load_map, tile_layer};
+use tiled_loader::{autotile, Map, MapLayer, WangSetType};
+
+const GRASS: u8 = 1;
+const WATER: u8 = 2;
+
+// A wang set with all 16 combinations of grass and water on the four
+// relevant positions. Bit `i` of the tile id is set if position `i` (in the
+// order of `WangId::corners` or `WangId::edges`) is water.
+fn load_terrain_map(set_type: &str, width: u32, height: u32) -> Map {
+    let mut tiles = String::new();
+    for id in 0..16 {
+        let colors: Vec<u8> = (0..4)
+            .map(|i| if id & (1 << i) != 0 { WATER } else { GRASS })
+            .collect();
+        let wangid = if set_type == "corner" {
+            format!(
+                "0,{},0,{},0,{},0,{}",
+                colors[0], colors[1], colors[2], colors[3]
+            )
+        } else {
+            format!(
+                "{},0,{},0,{},0,{},0",
+                colors[0], colors[1], colors[2], colors[3]
+            )
+        };
+        tiles.push_str(&format!(
+            "<wangtile tileid=\"{}\" wangid=\"{}\"/>\n",
+            id, wangid
+        ));
+    }
+
+    let tileset = format!(
+        r##"<tileset firstgid="5" name="terrain" tilewidth="16" tileheight="16" tilecount="16" columns="4">
+  <wangsets>
+   <wangset name="terrain" type="{}" tile="-1">
+    <wangcolor name="grass" color="#00ff00" tile="-1" probability="1"/>
+    <wangcolor name="water" color="#0000ff" tile="-1" probability="1"/>
+    {}
+   </wangset>
+  </wangsets>
+ </tileset>"##,
+        set_type, tiles
+    );
+    let attributes = format!(
+        r#"orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="16" tileheight="16" nextobjectid="1""#,
+        width, height
+    );
+
+    load_map(
+        &attributes,
+        &(tileset + &tile_layer("ground", width, height, "")),
+    )
+}
+
+// Runs the autotiler on the only layer of the map and returns, for every
+// cell, which of the four positions of its tile are water.
+fn paint(map: &mut Map, colors: &[u8]) -> Vec<[bool; 4]> {
+    let tileset = &map.tilesets[0];
+    let wangset = &tileset.wangsets[0];
+    let layer = match map.layers[0] {
+        MapLayer::Layer(ref mut layer) => layer,
+        _ => panic!("expected a tile layer"),
+    };
+
+    autotile(layer, tileset, wangset, colors, || 0.0).unwrap();
+
+    layer
+        .data
+        .tile_gids
+        .iter()
+        .map(|&gid| {
+            let id = gid - tileset.firstgid;
+            [id & 1 != 0, id & 2 != 0, id & 4 != 0, id & 8 != 0]
+        })
+        .collect()
+}
+
+#[test]
+fn corner_set_shares_corners_with_lower_color() {
+    let mut map = load_terrain_map("corner", 5, 5);
+    assert_eq!(map.tilesets[0].wangsets[0].type_, WangSetType::Corner);
+
+    // a 3x3 lake in the middle of a meadow
+    let mut colors = vec![GRASS; 25];
+    for y in 1..4 {
+        for x in 1..4 {
+            colors[y * 5 + x] = WATER;
+        }
+    }
+
+    let tiles = paint(&mut map, &colors);
+    let at = |x: usize, y: usize| tiles[y * 5 + x];
+
+    // corners are top-right, bottom-right, bottom-left, top-left; a corner
+    // is only water if all cells around it are
+    assert_eq!(at(1, 1), [false, true, false, false]);
+    assert_eq!(at(2, 1), [false, true, true, false]);
+    assert_eq!(at(3, 1), [false, false, true, false]);
+    assert_eq!(at(1, 2), [true, true, false, false]);
+    assert_eq!(at(2, 2), [true, true, true, true]);
+    assert_eq!(at(3, 3), [false, false, false, true]);
+
+    // grass cells only touch water at corners shared with water cells, which
+    // are grass as it has the lower index
+    assert_eq!(at(0, 0), [false; 4]);
+    assert_eq!(at(4, 2), [false; 4]);
+}
+
+#[test]
+fn edge_set_shares_edges_with_lower_color() {
+    let mut map = load_terrain_map("edge", 4, 3);
+    assert_eq!(map.tilesets[0].wangsets[0].type_, WangSetType::Edge);
+
+    // two water cells next to each other
+    let mut colors = vec![GRASS; 12];
+    colors[4 + 1] = WATER;
+    colors[4 + 2] = WATER;
+
+    let tiles = paint(&mut map, &colors);
+
+    // edges are top, right, bottom, left; only the edge between the two
+    // water cells is water
+    assert_eq!(tiles[4 + 1], [false, true, false, false]);
+    assert_eq!(tiles[4 + 2], [false, false, false, true]);
+    assert!(tiles
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != 5 && i != 6)
+        .all(|(_, t)| *t == [false; 4]));
+}
+
+#[test]
+fn empty_cells_stay_empty() {
+    let mut map = load_terrain_map("corner", 2, 1);
+    let tileset = &map.tilesets[0];
+    let layer = match map.layers[0] {
+        MapLayer::Layer(ref mut layer) => layer,
+        _ => panic!("expected a tile layer"),
+    };
+
+    autotile(layer, tileset, &tileset.wangsets[0], &[0, WATER], || 0.0).unwrap();
+
+    // the empty cell doesn't count as a neighbour, so the water cell is
+    // surrounded by water
+    assert_eq!(layer.data.tile_gids, vec![0, tileset.firstgid + 15]);
+}
@@ -0,0 +1,39 @@
+extern crate tiled_loader;
+
+mod common;
+
+use common::{load_map, parse_grid, tile_layer};
+
+#[test]
+fn merge_rects() {
+    let grid = parse_grid(&["##..", "##.#", "...#"]);
+    assert_eq!(grid.merge_rects(), vec![(0, 0, 2, 2), (3, 1, 1, 2)]);
+
+    // rows are grown first, so an L shape is split below its top row
+    let grid = parse_grid(&["###", "#..", "#.."]);
+    assert_eq!(grid.merge_rects(), vec![(0, 0, 3, 1), (0, 1, 1, 2)]);
+
+    assert_eq!(parse_grid(&["...", "..."]).merge_rects(), vec![]);
+}
+
+#[test]
+fn grid_from_tile_predicate() {
+    let map = load_map(
+        r#"orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="16" tileheight="16""#,
+        &format!(
+            r#"<tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16" tilecount="2" columns="2">
+  <image source="tiles.png" width="32" height="16"/>
+ </tileset>
+ {}
+ {}"#,
+            tile_layer("Ground", 3, 2, "1,1,1,1,2,1"),
+            tile_layer("Walls", 3, 2, "2,0,0,0,0,0")
+        ),
+    );
+
+    let grid = map.collision_grid(|_| true, |_, local_id| local_id == 1);
+    assert_eq!(grid.merge_rects(), vec![(0, 0, 1, 1), (1, 1, 1, 1)]);
+
+    let grid = map.collision_grid(|layer| layer.common.name == "Ground", |_, id| id == 1);
+    assert_eq!(grid.merge_rects(), vec![(1, 1, 1, 1)]);
+}
@@ -0,0 +1,43 @@
+// Helpers shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use tiled_loader::{self, CollisionGrid, Map};
+
+/// Builds a map file with the given attributes on `<map>`, around `content`.
+pub fn tmx(attributes: &str, content: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.0" {}>
+{}
+</map>"#,
+        attributes, content
+    )
+}
+
+/// Loads the map built by `tmx`.
+pub fn load_map(attributes: &str, content: &str) -> Map {
+    tiled_loader::load_from_str(&tmx(attributes, content)).unwrap()
+}
+
+/// Builds a tile layer with CSV encoded data.
+pub fn tile_layer(name: &str, width: u32, height: u32, csv: &str) -> String {
+    format!(
+        r#"<layer name="{}" width="{}" height="{}">
+ <data encoding="csv">{}</data>
+</layer>"#,
+        name, width, height, csv
+    )
+}
+
+/// Builds a collision grid from rows of cells, in which `#` is solid.
+pub fn parse_grid(rows: &[&str]) -> CollisionGrid {
+    let mut grid = CollisionGrid::new(rows[0].len() as u32, rows.len() as u32);
+
+    for (y, row) in rows.iter().enumerate() {
+        for (x, cell) in row.chars().enumerate() {
+            grid.set(x as u32, y as u32, cell == '#');
+        }
+    }
+
+    grid
+}
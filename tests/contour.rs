@@ -0,0 +1,90 @@
+extern crate tiled_loader;
+
+mod common;
+
+use common::parse_grid;
+
+// Twice the signed area, positive for polygons that run clockwise with the y
+// axis pointing down.
+fn signed_area(points: &[(f32, f32)]) -> f32 {
+    let next = points.iter().cycle().skip(1);
+    points
+        .iter()
+        .zip(next)
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum()
+}
+
+fn sorted(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    points
+}
+
+#[test]
+fn straight_runs_are_merged() {
+    let contours = parse_grid(&["####"]).contours(2.0, 3.0);
+
+    assert_eq!(contours.len(), 1);
+    assert!(contours[0].holes.is_empty());
+
+    let outline = &contours[0].outline;
+    assert_eq!(
+        sorted(outline),
+        vec![(0.0, 0.0), (0.0, 3.0), (8.0, 0.0), (8.0, 3.0)]
+    );
+    assert_eq!(signed_area(outline), 2.0 * 24.0);
+}
+
+#[test]
+fn ring_has_a_hole() {
+    let contours = parse_grid(&[".....", ".###.", ".#.#.", ".###.", "....."]).contours(16.0, 16.0);
+
+    assert_eq!(contours.len(), 1);
+    let contour = &contours[0];
+
+    assert_eq!(
+        sorted(&contour.outline),
+        vec![(16.0, 16.0), (16.0, 64.0), (64.0, 16.0), (64.0, 64.0)]
+    );
+    assert!(signed_area(&contour.outline) > 0.0);
+
+    assert_eq!(contour.holes.len(), 1);
+    let hole = &contour.holes[0];
+    assert_eq!(
+        sorted(hole),
+        vec![(32.0, 32.0), (32.0, 48.0), (48.0, 32.0), (48.0, 48.0)]
+    );
+    assert!(signed_area(hole) < 0.0);
+}
+
+#[test]
+fn concave_outline_keeps_inner_corners() {
+    // an L shape has 6 corners, one of them pointing inwards
+    let contours = parse_grid(&["#.", "##"]).contours(1.0, 1.0);
+
+    assert_eq!(contours.len(), 1);
+    assert_eq!(
+        sorted(&contours[0].outline),
+        vec![
+            (0.0, 0.0),
+            (0.0, 2.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (2.0, 1.0),
+            (2.0, 2.0),
+        ]
+    );
+    assert_eq!(signed_area(&contours[0].outline), 2.0 * 3.0);
+}
+
+#[test]
+fn diagonal_cells_are_separate() {
+    let contours = parse_grid(&["#.", ".#"]).contours(1.0, 1.0);
+
+    assert_eq!(contours.len(), 2);
+    for contour in &contours {
+        assert_eq!(contour.outline.len(), 4);
+        assert!(contour.holes.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::Map;
+
+fn load_map(attributes: &str) -> Map {
+    common::load_map(
+        &format!(r#"{} width="10" height="10""#, attributes),
+        &common::tile_layer("Tile Layer 1", 10, 10, ""),
+    )
+}
+
+// Converting the center of each tile back should result in the same tile.
+fn assert_round_trip(map: &Map) {
+    let (tw, th) = (map.tilewidth as f32, map.tileheight as f32);
+
+    for y in -3..12 {
+        for x in -3..12 {
+            let (px, py) = map.tile_to_pixel(x, y);
+            assert_eq!(
+                map.pixel_to_tile(px + tw / 2.0, py + th / 2.0),
+                (x, y),
+                "round trip of tile ({}, {}) at pixel ({}, {})",
+                x,
+                y,
+                px,
+                py
+            );
+        }
+    }
+}
+
+#[test]
+fn orthogonal() {
+    let map = load_map(r#"orientation="orthogonal" tilewidth="32" tileheight="16""#);
+
+    assert_eq!(map.tile_to_pixel(3, 2), (96.0, 32.0));
+    assert_eq!(map.pixel_to_tile(95.9, 32.0), (2, 2));
+    assert_eq!(map.pixel_to_tile(-0.5, 0.0), (-1, 0));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn isometric() {
+    let map = load_map(r#"orientation="isometric" tilewidth="64" tileheight="32""#);
+
+    // the top corner of (0, 0) is at the horizontal center of the map
+    assert_eq!(map.tile_to_pixel(0, 0), (288.0, 0.0));
+    assert_eq!(map.tile_to_pixel(1, 0), (320.0, 16.0));
+    assert_eq!(map.tile_to_pixel(0, 1), (256.0, 16.0));
+    assert_eq!(map.tile_to_pixel(0, 9), (0.0, 144.0));
+
+    // corners of the bounding box of (0, 0) belong to neighbouring tiles
+    assert_eq!(map.pixel_to_tile(290.0, 1.0), (-1, 0));
+    assert_eq!(map.pixel_to_tile(350.0, 1.0), (0, -1));
+    assert_eq!(map.pixel_to_tile(290.0, 31.0), (0, 1));
+    assert_eq!(map.pixel_to_tile(350.0, 31.0), (1, 0));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn staggered_y_odd() {
+    let map = load_map(
+        r#"orientation="staggered" staggeraxis="y" staggerindex="odd" tilewidth="64" tileheight="32""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (0.0, 0.0));
+    assert_eq!(map.tile_to_pixel(0, 1), (32.0, 16.0));
+    assert_eq!(map.tile_to_pixel(1, 2), (64.0, 32.0));
+
+    assert_eq!(map.pixel_to_tile(2.0, 1.0), (-1, -1));
+    assert_eq!(map.pixel_to_tile(62.0, 31.0), (0, 1));
+    assert_eq!(map.pixel_to_tile(2.0, 31.0), (-1, 1));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn staggered_y_even() {
+    let map = load_map(
+        r#"orientation="staggered" staggeraxis="y" staggerindex="even" tilewidth="64" tileheight="32""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (32.0, 0.0));
+    assert_eq!(map.tile_to_pixel(0, 1), (0.0, 16.0));
+
+    // row -1 is not staggered, so the top corners of the bounding box of
+    // (0, 0) belong to (0, -1) and (1, -1)
+    assert_eq!(map.pixel_to_tile(34.0, 1.0), (0, -1));
+    assert_eq!(map.pixel_to_tile(94.0, 1.0), (1, -1));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn staggered_x_odd() {
+    let map = load_map(
+        r#"orientation="staggered" staggeraxis="x" staggerindex="odd" tilewidth="64" tileheight="32""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (0.0, 0.0));
+    assert_eq!(map.tile_to_pixel(1, 0), (32.0, 16.0));
+    assert_eq!(map.tile_to_pixel(2, 1), (64.0, 32.0));
+
+    assert_eq!(map.pixel_to_tile(62.0, 31.0), (1, 0));
+    assert_eq!(map.pixel_to_tile(62.0, 1.0), (1, -1));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn staggered_x_even() {
+    let map = load_map(
+        r#"orientation="staggered" staggeraxis="x" staggerindex="even" tilewidth="64" tileheight="32""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (0.0, 16.0));
+    assert_eq!(map.tile_to_pixel(1, 0), (32.0, 0.0));
+
+    assert_eq!(map.pixel_to_tile(2.0, 17.0), (-1, 0));
+    assert_eq!(map.pixel_to_tile(62.0, 17.0), (1, 0));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn hexagonal_y_odd() {
+    let map = load_map(
+        r#"orientation="hexagonal" staggeraxis="y" staggerindex="odd" hexsidelength="16" tilewidth="28" tileheight="32""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (0.0, 0.0));
+    assert_eq!(map.tile_to_pixel(0, 1), (14.0, 24.0));
+    assert_eq!(map.tile_to_pixel(1, 2), (28.0, 48.0));
+
+    // top-left corner of the bounding box of (0, 1) is part of (0, 0)
+    assert_eq!(map.pixel_to_tile(15.0, 25.0), (0, 0));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn hexagonal_y_even() {
+    let map = load_map(
+        r#"orientation="hexagonal" staggeraxis="y" staggerindex="even" hexsidelength="16" tilewidth="28" tileheight="32""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (14.0, 0.0));
+    assert_eq!(map.tile_to_pixel(0, 1), (0.0, 24.0));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn hexagonal_x_odd() {
+    let map = load_map(
+        r#"orientation="hexagonal" staggeraxis="x" staggerindex="odd" hexsidelength="16" tilewidth="32" tileheight="28""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (0.0, 0.0));
+    assert_eq!(map.tile_to_pixel(1, 0), (24.0, 14.0));
+    assert_eq!(map.tile_to_pixel(2, 1), (48.0, 28.0));
+
+    // top-left corner of the bounding box of (1, 0) is part of (0, 0)
+    assert_eq!(map.pixel_to_tile(25.0, 15.0), (0, 0));
+    assert_round_trip(&map);
+}
+
+#[test]
+fn hexagonal_x_even() {
+    let map = load_map(
+        r#"orientation="hexagonal" staggeraxis="x" staggerindex="even" hexsidelength="16" tilewidth="32" tileheight="28""#,
+    );
+
+    assert_eq!(map.tile_to_pixel(0, 0), (0.0, 14.0));
+    assert_eq!(map.tile_to_pixel(1, 0), (24.0, 0.0));
+    assert_round_trip(&map);
+}
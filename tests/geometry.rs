@@ -0,0 +1,113 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{Map, MapLayer, Object};
+
+fn load_map(orientation: &str, objects: &str) -> Map {
+    common::load_map(
+        &format!(
+            r#"orientation="{}" width="4" height="4" tilewidth="32" tileheight="16" nextobjectid="10""#,
+            orientation
+        ),
+        &format!(
+            r#"<tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  <image source="tiles.png" width="32" height="32"/>
+ </tileset>
+ <objectgroup id="1" name="objects">
+  {}
+ </objectgroup>"#,
+            objects
+        ),
+    )
+}
+
+fn first_object(map: &Map) -> &Object {
+    match map.layers[0] {
+        MapLayer::ObjectGroup(ref group) => &group.objects[0],
+        _ => panic!("expected an object group"),
+    }
+}
+
+fn assert_close(actual: &[(f32, f32)], expected: &[(f32, f32)]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a.0 - e.0).abs() < 0.01 && (a.1 - e.1).abs() < 0.01,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn rotated_rectangle() {
+    let map = load_map(
+        "orthogonal",
+        r#"<object id="1" x="10" y="20" width="20" height="10" rotation="90"/>"#,
+    );
+    let object = first_object(&map);
+
+    assert_close(
+        &object.world_vertices(&map, 16),
+        &[(10.0, 20.0), (10.0, 40.0), (0.0, 40.0), (0.0, 20.0)],
+    );
+
+    let (x, y, width, height) = object.world_bounds(&map);
+    assert_close(&[(x, y), (width, height)], &[(0.0, 20.0), (10.0, 20.0)]);
+
+    assert!(object.contains_point(&map, (5.0, 30.0)));
+    assert!(!object.contains_point(&map, (15.0, 25.0)));
+}
+
+#[test]
+fn ellipse() {
+    let map = load_map(
+        "orthogonal",
+        r#"<object id="1" x="0" y="0" width="20" height="10">
+   <ellipse/>
+  </object>"#,
+    );
+    let object = first_object(&map);
+
+    assert_eq!(object.world_vertices(&map, 12).len(), 12);
+    assert_eq!(object.world_bounds(&map), (0.0, 0.0, 20.0, 10.0));
+
+    assert!(object.contains_point(&map, (10.0, 5.0)));
+    assert!(object.contains_point(&map, (19.0, 5.0)));
+    assert!(!object.contains_point(&map, (1.0, 1.0)));
+}
+
+#[test]
+fn isometric_rectangle_is_projected() {
+    // one tile in object coordinates, which use the tile height as unit on
+    // both axes
+    let map = load_map(
+        "isometric",
+        r#"<object id="1" x="0" y="0" width="16" height="16"/>"#,
+    );
+    let object = first_object(&map);
+
+    assert_close(
+        &object.world_vertices(&map, 16),
+        &[(64.0, 0.0), (80.0, 8.0), (64.0, 16.0), (48.0, 8.0)],
+    );
+    assert_eq!(object.world_bounds(&map), (48.0, 0.0, 32.0, 16.0));
+
+    assert!(object.contains_point(&map, (64.0, 8.0)));
+    assert!(!object.contains_point(&map, (50.0, 1.0)));
+}
+
+#[test]
+fn isometric_tile_object_is_anchored_at_bottom_center() {
+    // without a size, the object has the size of its tile
+    let map = load_map("isometric", r#"<object id="1" gid="1" x="16" y="16"/>"#);
+    let object = first_object(&map);
+
+    assert_close(
+        &object.world_vertices(&map, 16),
+        &[(48.0, -16.0), (80.0, -16.0), (80.0, 16.0), (48.0, 16.0)],
+    );
+    assert!(object.contains_point(&map, (64.0, 0.0)));
+}
@@ -0,0 +1,112 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{HexGrid, StaggerAxis, StaggerIndex};
+
+fn hex_grid(axis: &str, index: &str) -> HexGrid {
+    let map = common::load_map(
+        &format!(
+            r#"orientation="hexagonal" staggeraxis="{}" staggerindex="{}" hexsidelength="16" tilewidth="32" tileheight="32" width="10" height="10""#,
+            axis, index
+        ),
+        &common::tile_layer("Tile Layer 1", 10, 10, ""),
+    );
+
+    map.hex_grid().unwrap()
+}
+
+fn sorted(mut tiles: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    tiles.sort();
+    tiles
+}
+
+fn assert_ring_is_neighbors(grid: &HexGrid, neighbors: [(i32, i32); 6]) {
+    assert_eq!(grid.neighbors((1, 1)), neighbors);
+    assert_eq!(sorted(grid.ring((1, 1), 1)), sorted(neighbors.to_vec()));
+}
+
+fn assert_rings(grid: &HexGrid) {
+    let center = (4, 4);
+
+    assert_eq!(grid.ring(center, 0), vec![center]);
+    assert_eq!(grid.range(center, 2).len(), 19);
+
+    for radius in 1..4 {
+        let ring = grid.ring(center, radius);
+
+        assert_eq!(ring.len(), 6 * radius as usize);
+        assert!(ring
+            .iter()
+            .all(|&tile| grid.distance(center, tile) == radius));
+    }
+}
+
+#[test]
+fn stagger_y_odd() {
+    let grid = hex_grid("y", "odd");
+    assert_eq!(grid.stagger_axis, StaggerAxis::Y);
+    assert_eq!(grid.stagger_index, StaggerIndex::Odd);
+
+    // odd rows are shifted right
+    assert_ring_is_neighbors(&grid, [(2, 1), (2, 0), (1, 0), (0, 1), (1, 2), (2, 2)]);
+    assert_rings(&grid);
+
+    assert_eq!(grid.distance((0, 1), (1, 0)), 1);
+    assert_eq!(grid.distance((0, 0), (3, 2)), 4);
+    assert_eq!(grid.distance((0, 0), (0, 4)), 4);
+    assert_eq!(grid.distance((0, 0), (5, 0)), 5);
+    assert_eq!(
+        grid.line((0, 0), (3, 0)),
+        vec![(0, 0), (1, 0), (2, 0), (3, 0)]
+    );
+}
+
+#[test]
+fn stagger_y_even() {
+    let grid = hex_grid("y", "even");
+    assert_eq!(grid.stagger_index, StaggerIndex::Even);
+
+    // even rows are shifted right
+    assert_ring_is_neighbors(&grid, [(2, 1), (1, 0), (0, 0), (0, 1), (0, 2), (1, 2)]);
+    assert_rings(&grid);
+
+    assert_eq!(grid.distance((0, 1), (1, 0)), 2);
+    assert_eq!(grid.distance((0, 0), (3, 2)), 4);
+    assert_eq!(grid.distance((0, 0), (0, 4)), 4);
+    assert_eq!(grid.distance((0, 0), (5, 0)), 5);
+}
+
+#[test]
+fn stagger_x_odd() {
+    let grid = hex_grid("x", "odd");
+    assert_eq!(grid.stagger_axis, StaggerAxis::X);
+
+    // odd columns are shifted down
+    assert_ring_is_neighbors(&grid, [(2, 2), (2, 1), (1, 0), (0, 1), (0, 2), (1, 2)]);
+    assert_rings(&grid);
+
+    assert_eq!(grid.distance((1, 0), (0, 1)), 1);
+    assert_eq!(grid.distance((0, 0), (2, 3)), 4);
+    assert_eq!(grid.distance((0, 0), (4, 0)), 4);
+    assert_eq!(grid.distance((0, 0), (0, 5)), 5);
+    assert_eq!(
+        grid.line((0, 0), (0, 3)),
+        vec![(0, 0), (0, 1), (0, 2), (0, 3)]
+    );
+}
+
+#[test]
+fn stagger_x_even() {
+    let grid = hex_grid("x", "even");
+    assert_eq!(grid.stagger_index, StaggerIndex::Even);
+
+    // even columns are shifted down
+    assert_ring_is_neighbors(&grid, [(2, 1), (2, 0), (1, 0), (0, 0), (0, 1), (1, 2)]);
+    assert_rings(&grid);
+
+    assert_eq!(grid.distance((1, 0), (0, 1)), 2);
+    assert_eq!(grid.distance((0, 0), (2, 3)), 4);
+    assert_eq!(grid.distance((0, 0), (4, 0)), 4);
+    assert_eq!(grid.distance((0, 0), (0, 5)), 5);
+}
@@ -0,0 +1,99 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{Map, MapLayer};
+
+fn load_map(orientation: &str, renderorder: &str, width: u32, height: u32, csv: &str) -> Map {
+    common::load_map(
+        &format!(
+            r#"orientation="{}" renderorder="{}" width="{}" height="{}" tilewidth="32" tileheight="16""#,
+            orientation, renderorder, width, height
+        ),
+        &common::tile_layer("Tile Layer 1", width, height, csv),
+    )
+}
+
+fn render_order(map: &Map) -> Vec<(u32, u32, u32)> {
+    match map.layers[0] {
+        MapLayer::Layer(ref layer) => layer
+            .tiles_in_render_order(map)
+            .map(|(x, y, gid)| (x, y, gid.gid()))
+            .collect(),
+        _ => panic!("expected a tile layer"),
+    }
+}
+
+#[test]
+fn orthogonal_render_order() {
+    let map = load_map("orthogonal", "left-up", 3, 2, "1,2,0,4,5,6");
+
+    // bottom row first, each from right to left, without empty tiles
+    assert_eq!(
+        render_order(&map),
+        vec![(2, 1, 6), (1, 1, 5), (0, 1, 4), (1, 0, 2), (0, 0, 1)]
+    );
+
+    let map = load_map("orthogonal", "right-down", 3, 2, "1,2,0,4,5,6");
+    assert_eq!(
+        render_order(&map),
+        vec![(0, 0, 1), (1, 0, 2), (0, 1, 4), (1, 1, 5), (2, 1, 6)]
+    );
+}
+
+#[test]
+fn isometric_ignores_render_order() {
+    // diagonal rows from the top corner down, each from left to right
+    for renderorder in &["right-down", "left-up"] {
+        let map = load_map("isometric", renderorder, 2, 2, "1,2,3,4");
+
+        assert_eq!(
+            render_order(&map),
+            vec![(0, 0, 1), (0, 1, 3), (1, 0, 2), (1, 1, 4)]
+        );
+    }
+}
+
+#[test]
+fn nested_group_inherits_offset_and_opacity() {
+    let map = common::load_map(
+        r#"orientation="orthogonal" renderorder="right-down" width="4" height="4" tilewidth="16" tileheight="16" nextobjectid="2""#,
+        r#"<group id="1" name="outer" offsetx="10" offsety="20" opacity="0.5">
+  <properties>
+   <property name="depth" type="int" value="1"/>
+  </properties>
+  <group id="2" name="inner" offsetx="5" offsety="-4" opacity="0.5">
+   <objectgroup id="3" name="objects">
+    <object id="1" x="0" y="0" width="4" height="4"/>
+   </objectgroup>
+  </group>
+ </group>"#,
+    );
+
+    let outer = match map.layers[0] {
+        MapLayer::Group(ref group) => group,
+        _ => panic!("expected a group"),
+    };
+    assert_eq!(outer.common.name, "outer");
+    assert!(outer.common.properties.is_some());
+    assert_eq!(outer.layers.len(), 1);
+
+    let inner = match outer.layers[0] {
+        MapLayer::Group(ref group) => group,
+        _ => panic!("expected a nested group"),
+    };
+    assert_eq!(inner.common.name, "inner");
+    assert_eq!((inner.common.offsetx, inner.common.offsety), (5.0, -4.0));
+    assert_eq!(outer.common.opacity * inner.common.opacity, 0.25);
+
+    let objects = match inner.layers[0] {
+        MapLayer::ObjectGroup(ref group) => group,
+        _ => panic!("expected an object group"),
+    };
+    assert_eq!(objects.objects.len(), 1);
+
+    // the object is moved by the offsets of both groups
+    let index = map.spatial_index(16.0);
+    assert_eq!(index.query_point((17.0, 18.0)).len(), 1);
+    assert!(index.query_point((2.0, 2.0)).is_empty());
+}
@@ -0,0 +1,38 @@
+extern crate tiled_loader;
+
+mod common;
+
+use common::{load_map, tile_layer};
+
+#[test]
+fn modern_attributes() {
+    let map = load_map(
+        r#"tiledversion="1.10.2" class="dungeon" orientation="orthogonal" renderorder="right-down" width="2" height="2" tilewidth="16" tileheight="16" infinite="0" parallaxoriginx="8" parallaxoriginy="-4" compressionlevel="6" nextlayerid="5" nextobjectid="9" someday="new""#,
+        &tile_layer("ground", 2, 2, "0,0,0,0"),
+    );
+
+    assert_eq!(map.class, Some("dungeon".to_string()));
+    assert!(!map.infinite);
+    assert_eq!((map.parallaxoriginx, map.parallaxoriginy), (8.0, -4.0));
+    assert_eq!(map.compressionlevel, 6);
+    assert_eq!((map.nextlayerid, map.nextobjectid), (5, 9));
+}
+
+#[test]
+fn old_maps_without_next_ids() {
+    let map = load_map(
+        r#"orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16""#,
+        r#"<layer id="3" name="ground" width="2" height="2">
+  <data encoding="csv">0,0,0,0</data>
+ </layer>
+ <objectgroup id="4" name="things">
+  <object id="7" x="0" y="0"/>
+  <object id="2" x="0" y="0"/>
+ </objectgroup>"#,
+    );
+
+    assert_eq!(map.class, None);
+    assert_eq!((map.parallaxoriginx, map.parallaxoriginy), (0.0, 0.0));
+    assert_eq!(map.compressionlevel, -1);
+    assert_eq!((map.nextlayerid, map.nextobjectid), (5, 8));
+}
@@ -0,0 +1,48 @@
+extern crate tiled_loader;
+
+mod common;
+
+use common::{load_map, tile_layer};
+use tiled_loader::{MapLayer, VERTEX_SIZE};
+
+#[test]
+fn quads_with_flipped_uvs() {
+    // the second tile is flipped horizontally
+    let map = load_map(
+        r#"orientation="orthogonal" renderorder="right-down" width="3" height="1" tilewidth="16" tileheight="16""#,
+        &format!(
+            r#"<tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16" tilecount="2" columns="2">
+  <image source="tiles.png" width="32" height="16"/>
+ </tileset>
+ {}"#,
+            tile_layer("Tile Layer 1", 3, 1, "1,0,2147483650")
+        ),
+    );
+
+    let batches = match map.layers[0] {
+        MapLayer::Layer(ref layer) => layer.build_mesh(&map),
+        _ => panic!("expected a tile layer"),
+    };
+    assert_eq!(batches.len(), 1);
+
+    let batch = &batches[0];
+    assert_eq!((batch.firstgid, batch.tile), (1, None));
+    assert_eq!(batch.vertices.len(), 2 * 4 * VERTEX_SIZE);
+    assert_eq!(batch.indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+
+    // top-left, bottom-left, bottom-right and top-right corner of each quad
+    let vertices: Vec<&[f32]> = batch.vertices.chunks(VERTEX_SIZE).collect();
+    assert_eq!(
+        vertices,
+        vec![
+            &[0.0, 0.0, 0.0, 0.0],
+            &[0.0, 16.0, 0.0, 1.0],
+            &[16.0, 16.0, 0.5, 1.0],
+            &[16.0, 0.0, 0.5, 0.0],
+            &[32.0, 0.0, 1.0, 0.0],
+            &[32.0, 16.0, 1.0, 1.0],
+            &[48.0, 16.0, 0.5, 1.0],
+            &[48.0, 0.0, 0.5, 0.0],
+        ]
+    );
+}
@@ -0,0 +1,147 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{Map, NavMesh};
+
+fn load_map(objects: &str) -> Map {
+    common::load_map(
+        r#"orientation="orthogonal" width="10" height="10" tilewidth="10" tileheight="10" nextobjectid="10""#,
+        &format!(
+            r#"<tileset firstgid="1" name="rocks" tilewidth="20" tileheight="20" tilecount="1" columns="1"/>
+ <objectgroup id="1" name="walkable">
+  {}
+ </objectgroup>"#,
+            objects
+        ),
+    )
+}
+
+fn nav_mesh(objects: &str) -> NavMesh {
+    load_map(objects).nav_mesh("walkable").unwrap()
+}
+
+fn assert_close(actual: (f32, f32), expected: (f32, f32)) {
+    assert!(
+        (actual.0 - expected.0).abs() < 0.01 && (actual.1 - expected.1).abs() < 0.01,
+        "expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+}
+
+// Whether all polygons are convex and run clockwise.
+fn all_convex(mesh: &NavMesh) -> bool {
+    mesh.polygons.iter().all(|polygon| {
+        let v = &polygon.vertices;
+        (0..v.len()).all(|i| {
+            let (a, b, c) = (v[i], v[(i + 1) % v.len()], v[(i + 2) % v.len()]);
+            (b.0 - a.0) * (c.1 - b.1) - (b.1 - a.1) * (c.0 - b.0) >= 0.0
+        })
+    })
+}
+
+#[test]
+fn concave_polygon_is_split_and_walked_around_its_corner() {
+    // an L shape: a corridor going down, then to the right
+    let mesh = nav_mesh(
+        r#"<object id="1" x="0" y="0">
+   <polygon points="0,0 30,0 30,70 100,70 100,100 0,100"/>
+  </object>"#,
+    );
+
+    assert!(mesh.polygons.len() >= 2);
+    assert!(all_convex(&mesh));
+
+    assert!(mesh.find_polygon((15.0, 10.0)).is_some());
+    assert!(mesh.find_polygon((90.0, 85.0)).is_some());
+    assert_eq!(mesh.find_polygon((60.0, 30.0)), None);
+
+    // every portal is known to both of its polygons
+    for (i, polygon) in mesh.polygons.iter().enumerate() {
+        for portal in &polygon.portals {
+            assert!(mesh.polygons[portal.polygon]
+                .portals
+                .iter()
+                .any(|p| p.polygon == i && p.left == portal.right && p.right == portal.left));
+        }
+    }
+
+    // the straight line would leave the mesh, so the path bends at the inner
+    // corner
+    let path = mesh.find_path((15.0, 10.0), (90.0, 85.0)).unwrap();
+    assert_eq!(path.len(), 3);
+    assert_close(path[0], (15.0, 10.0));
+    assert_close(path[1], (30.0, 70.0));
+    assert_close(path[2], (90.0, 85.0));
+}
+
+#[test]
+fn path_goes_around_obstacle() {
+    let mesh = nav_mesh(
+        r#"<object id="1" x="0" y="0">
+   <polygon points="0,0 100,0 100,60 0,60"/>
+  </object>
+  <object id="2" x="40" y="20" width="20" height="20"/>"#,
+    );
+
+    assert!(all_convex(&mesh));
+    assert_eq!(mesh.find_polygon((50.0, 30.0)), None);
+
+    let path = mesh.find_path((10.0, 30.0), (90.0, 30.0)).unwrap();
+    assert_eq!(path.len(), 4);
+
+    // it touches the obstacle at two corners on the same side, both of which
+    // are equally short
+    let side = if path[1].1 < 30.0 { 20.0 } else { 40.0 };
+    assert_close(path[1], (40.0, side));
+    assert_close(path[2], (60.0, side));
+}
+
+#[test]
+fn tile_objects_are_obstacles() {
+    // tile objects are anchored at their bottom-left corner, so this one
+    // covers the same area as the rectangle above. Without a size, it has
+    // the size of its tile.
+    let tile_objects = [
+        r#"<object id="2" gid="1" x="40" y="40" width="20" height="20"/>"#,
+        r#"<object id="2" gid="1" x="40" y="40"/>"#,
+    ];
+
+    for tile_object in &tile_objects {
+        let mesh = nav_mesh(&format!(
+            r#"<object id="1" x="0" y="0">
+   <polygon points="0,0 100,0 100,60 0,60"/>
+  </object>
+  {}"#,
+            tile_object
+        ));
+
+        assert_eq!(mesh.find_polygon((50.0, 30.0)), None);
+        assert!(mesh.find_polygon((50.0, 10.0)).is_some());
+        assert!(mesh.find_polygon((50.0, 50.0)).is_some());
+
+        let path = mesh.find_path((10.0, 30.0), (90.0, 30.0)).unwrap();
+        assert_eq!(path.len(), 4);
+    }
+}
+
+#[test]
+fn obstacle_cutting_polygon_in_two() {
+    let mesh = nav_mesh(
+        r#"<object id="1" x="0" y="0">
+   <polygon points="0,0 100,0 100,50 0,50"/>
+  </object>
+  <object id="2" x="40" y="-10" width="20" height="70"/>"#,
+    );
+
+    assert!(mesh.find_polygon((10.0, 25.0)).is_some());
+    assert!(mesh.find_polygon((90.0, 25.0)).is_some());
+    assert_eq!(mesh.find_polygon((50.0, 25.0)), None);
+
+    assert_eq!(mesh.find_path((10.0, 25.0), (90.0, 25.0)), None);
+
+    // both halves are still walkable on their own
+    let path = mesh.find_path((10.0, 10.0), (30.0, 40.0)).unwrap();
+    assert_eq!(path.len(), 2);
+}
@@ -0,0 +1,122 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{Connectivity, Map, NavGrid};
+
+fn load_map(attributes: &str, width: u32, height: u32, data: &str) -> Map {
+    common::load_map(
+        &format!(r#"{} width="{}" height="{}""#, attributes, width, height),
+        &format!(
+            r#"<tileset firstgid="1" name="walls" tilewidth="64" tileheight="32" tilecount="2" columns="2">
+  <tile id="1">
+   <properties>
+    <property name="solid" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ {}"#,
+            common::tile_layer("ground", width, height, data)
+        ),
+    )
+}
+
+fn nav_grid(map: &Map, connectivity: Connectivity) -> NavGrid {
+    map.nav_grid(|_| true, NavGrid::property_cost, connectivity)
+}
+
+#[test]
+fn orthogonal_walls_from_properties() {
+    // a wall in the middle column, open at the bottom
+    let map = load_map(
+        r#"orientation="orthogonal" tilewidth="64" tileheight="32""#,
+        5,
+        3,
+        "1,1,2,1,1,\n1,1,2,1,1,\n1,1,1,1,1",
+    );
+    let grid = nav_grid(&map, Connectivity::Four);
+
+    assert_eq!(grid.cost(2, 0), None);
+    assert_eq!(grid.cost(2, 2), Some(1.0));
+
+    let path = grid.find_path((0, 0), (4, 0)).unwrap();
+    assert_eq!(path.len(), 9);
+    assert_eq!(path[0], (0, 0));
+    assert_eq!(path[8], (4, 0));
+    assert!(path.contains(&(2, 2)));
+
+    assert_eq!(grid.find_path((0, 0), (2, 0)), None);
+}
+
+// Whether two tiles of a staggered map with 64x32 tiles share an edge, from
+// their positions on screen.
+fn staggered_neighbors(map: &Map, a: (u32, u32), b: (u32, u32)) -> bool {
+    let (ax, ay) = map.tile_to_pixel(a.0 as i32, a.1 as i32);
+    let (bx, by) = map.tile_to_pixel(b.0 as i32, b.1 as i32);
+
+    (ax - bx).abs() == 32.0 && (ay - by).abs() == 16.0
+}
+
+#[test]
+fn staggered_detour() {
+    let map = load_map(
+        r#"orientation="staggered" staggeraxis="y" staggerindex="odd" tilewidth="64" tileheight="32""#,
+        5,
+        7,
+        "",
+    );
+    let mut grid = nav_grid(&map, Connectivity::Four);
+
+    // straight down on screen, zig-zagging between the staggered rows
+    let path = grid.find_path((0, 0), (0, 6)).unwrap();
+    assert_eq!(path.len(), 7);
+    assert!(path
+        .windows(2)
+        .all(|step| staggered_neighbors(&map, step[0], step[1])));
+
+    // a wall across the map with a gap at the right
+    for x in 0..4 {
+        grid.set_cost(x, 3, None);
+    }
+
+    let path = grid.find_path((0, 0), (0, 6)).unwrap();
+    assert!(path.contains(&(4, 3)));
+    assert!(path
+        .windows(2)
+        .all(|step| staggered_neighbors(&map, step[0], step[1])));
+
+    grid.set_cost(4, 3, None);
+    assert_eq!(grid.find_path((0, 0), (0, 6)), None);
+}
+
+#[test]
+fn hexagonal_detour() {
+    let map = load_map(
+        r#"orientation="hexagonal" hexsidelength="16" staggeraxis="y" staggerindex="odd" tilewidth="28" tileheight="32""#,
+        6,
+        5,
+        "",
+    );
+    let hex = map.hex_grid().unwrap();
+    let mut grid = nav_grid(&map, Connectivity::Four);
+
+    let steps_are_neighbors = |path: &[(u32, u32)]| {
+        path.windows(2).all(|step| {
+            let a = (step[0].0 as i32, step[0].1 as i32);
+            let b = (step[1].0 as i32, step[1].1 as i32);
+            hex.distance(a, b) == 1
+        })
+    };
+
+    let path = grid.find_path((0, 2), (4, 2)).unwrap();
+    assert_eq!(path.len(), 5);
+    assert!(steps_are_neighbors(&path));
+
+    // going around a blocked tile takes one more step
+    grid.set_cost(2, 2, None);
+
+    let path = grid.find_path((0, 2), (4, 2)).unwrap();
+    assert_eq!(path.len(), 6);
+    assert!(!path.contains(&(2, 2)));
+    assert!(steps_are_neighbors(&path));
+}
@@ -0,0 +1,180 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{LoadOptions, LoadWarning, Map, MapLayer, Project, Properties, Property};
+
+const PROJECT: &str = r#"{
+    "propertyTypes": [
+        {
+            "id": 1,
+            "name": "Difficulty",
+            "type": "enum",
+            "storageType": "string",
+            "values": ["Easy", "Hard"],
+            "valuesAsFlags": false
+        },
+        {
+            "id": 2,
+            "name": "Element",
+            "type": "enum",
+            "storageType": "int",
+            "values": ["Fire", "Water", "Earth"],
+            "valuesAsFlags": false
+        },
+        {
+            "id": 3,
+            "name": "Weapon",
+            "type": "class",
+            "useAs": ["property"],
+            "members": [
+                { "name": "damage", "type": "float", "value": 2 },
+                { "name": "element", "type": "int", "propertyType": "Element", "value": 0 }
+            ]
+        },
+        {
+            "id": 4,
+            "name": "Stats",
+            "type": "class",
+            "useAs": ["property", "object"],
+            "members": [
+                { "name": "strength", "type": "int", "value": 5 },
+                { "name": "speed", "type": "float", "value": 1.5 },
+                { "name": "weapon", "type": "class", "propertyType": "Weapon", "value": { "damage": 3 } }
+            ]
+        }
+    ]
+}"#;
+
+fn load_map(properties: &str, objects: &str) -> Map {
+    let map_str = common::tmx(
+        r#"tiledversion="1.10.2" orientation="orthogonal" width="10" height="10" tilewidth="16" tileheight="16" nextobjectid="10""#,
+        &format!(
+            r#"<properties>
+  {}
+ </properties>
+ <objectgroup id="1" name="objects">
+  {}
+ </objectgroup>"#,
+            properties, objects
+        ),
+    );
+
+    let options = LoadOptions {
+        project: Some(Project::load_from_str(PROJECT).unwrap()),
+        ..LoadOptions::default()
+    };
+
+    tiled_loader::load_from_str_with(&map_str, &options).unwrap()
+}
+
+fn class_members<'a>(properties: &'a Properties, name: &str) -> &'a Properties {
+    match properties.get(name) {
+        Some(&Property::Class { ref properties, .. }) => properties,
+        p => panic!("expected class property '{}', got {:?}", name, p),
+    }
+}
+
+#[test]
+fn class_defaults_are_filled_in() {
+    let map = load_map(
+        r#"<property name="stats" type="class" propertytype="Stats">
+   <properties>
+    <property name="strength" type="int" value="7"/>
+   </properties>
+  </property>"#,
+        "",
+    );
+
+    assert!(map.warnings.is_empty(), "{:?}", map.warnings);
+
+    let stats = class_members(map.properties.as_ref().unwrap(), "stats");
+    assert_eq!(stats.get("strength"), Some(&Property::Int(7)));
+    assert_eq!(stats.get("speed"), Some(&Property::Float(1.5)));
+
+    // the default of the member overrides the default of its class
+    let weapon = class_members(stats, "weapon");
+    assert_eq!(weapon.get("damage"), Some(&Property::Float(3.0)));
+    assert_eq!(weapon.get("element"), Some(&Property::Int(0)));
+    assert_eq!(weapon.property_type("element"), Some("Element"));
+}
+
+#[test]
+fn objects_get_defaults_of_their_class() {
+    let map = load_map(
+        "",
+        r#"<object id="1" class="Stats" x="0" y="0">
+   <properties>
+    <property name="speed" type="float" value="4"/>
+   </properties>
+  </object>"#,
+    );
+
+    let object = match map.layers[0] {
+        MapLayer::ObjectGroup(ref group) => &group.objects[0],
+        _ => panic!("expected an object group"),
+    };
+    let properties = map.effective_properties(object);
+
+    assert_eq!(properties.get("speed"), Some(&Property::Float(4.0)));
+    assert_eq!(properties.get("strength"), Some(&Property::Int(5)));
+    assert!(properties.get("weapon").is_some());
+
+    // the object itself is left as it was saved
+    assert!(object
+        .properties
+        .as_ref()
+        .unwrap()
+        .get("strength")
+        .is_none());
+}
+
+#[test]
+fn enum_values_are_checked() {
+    let map = load_map(
+        r#"<property name="difficulty" propertytype="Difficulty" value="Hard"/>
+  <property name="element" type="int" propertytype="Element" value="2"/>"#,
+        "",
+    );
+    assert!(map.warnings.is_empty(), "{:?}", map.warnings);
+
+    let map = load_map(
+        r#"<property name="difficulty" propertytype="Difficulty" value="Nightmare"/>
+  <property name="element" type="int" propertytype="Element" value="3"/>"#,
+        "",
+    );
+
+    assert_eq!(
+        map.warnings,
+        vec![
+            LoadWarning::InvalidEnumValue {
+                property: "difficulty".to_string(),
+                propertytype: "Difficulty".to_string(),
+            },
+            LoadWarning::InvalidEnumValue {
+                property: "element".to_string(),
+                propertytype: "Element".to_string(),
+            },
+        ]
+    );
+
+    // the values are kept
+    let properties = map.properties.as_ref().unwrap();
+    assert_eq!(properties.get_str("difficulty"), Ok("Nightmare"));
+}
+
+#[test]
+fn unknown_types_are_reported() {
+    let map = load_map(
+        r#"<property name="loot" type="class" propertytype="Chest"/>"#,
+        "",
+    );
+
+    assert_eq!(
+        map.warnings,
+        vec![LoadWarning::UnknownPropertyType {
+            property: "loot".to_string(),
+            propertytype: "Chest".to_string(),
+        }]
+    );
+}
@@ -0,0 +1,33 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{Map, Property};
+
+fn load_map(properties: &str) -> Map {
+    common::load_map(
+        r#"orientation="orthogonal" width="10" height="10" tilewidth="16" tileheight="16""#,
+        &format!("<properties>\n  {}\n </properties>", properties),
+    )
+}
+
+#[test]
+fn multiline_strings_are_stored_as_text() {
+    let map = load_map(
+        r#"<property name="description">A dark cave.
+Watch your step!</property>
+  <property name="title" value="Cave"/>
+  <property name="note" value=""/>"#,
+    );
+    let properties = map.properties.as_ref().unwrap();
+
+    assert_eq!(
+        properties.get_str("description"),
+        Ok("A dark cave.\nWatch your step!")
+    );
+    assert_eq!(properties.get_str("title"), Ok("Cave"));
+    assert_eq!(
+        properties.get("note"),
+        Some(&Property::String(String::new()))
+    );
+}
@@ -0,0 +1,201 @@
+extern crate tiled_loader;
+#[macro_use]
+extern crate serde_derive;
+
+mod common;
+
+use tiled_loader::{Color, Map, PropertyError};
+
+fn load_map(properties: &str) -> Map {
+    common::load_map(
+        r#"orientation="orthogonal" width="10" height="10" tilewidth="16" tileheight="16""#,
+        &format!("<properties>\n  {}\n </properties>", properties),
+    )
+}
+
+const PROPERTIES: &str = r##"<property name="name" value="Dungeon"/>
+  <property name="speed" type="float" value="1.5"/>
+  <property name="lives" type="int" value="3"/>
+  <property name="hidden" type="bool" value="true"/>
+  <property name="tint" type="color" value="#80ff0000"/>
+  <property name="target" type="object" value="12"/>
+  <property name="difficulty" propertytype="Difficulty" value="Hard"/>
+  <property name="element" type="int" propertytype="Element" value="1"/>
+  <property name="stats" type="class" propertytype="Stats">
+   <properties>
+    <property name="strength" type="int" value="7"/>
+    <property name="weapon" type="class" propertytype="Weapon">
+     <properties>
+      <property name="damage" type="float" value="2.5"/>
+     </properties>
+    </property>
+   </properties>
+  </property>"##;
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Difficulty {
+    Easy,
+    Hard,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Element {
+    Fire,
+    Water,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Weapon {
+    damage: f32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Stats {
+    strength: u8,
+    weapon: Weapon,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Level {
+    name: String,
+    speed: f32,
+    lives: u32,
+    hidden: bool,
+    tint: Color,
+    target: u32,
+    difficulty: Difficulty,
+    element: Element,
+    stats: Stats,
+    music: Option<String>,
+    #[serde(default)]
+    score: i64,
+}
+
+#[test]
+fn deserialize_struct() {
+    let map = load_map(PROPERTIES);
+    let level: Level = map.properties.as_ref().unwrap().deserialize_into().unwrap();
+
+    assert_eq!(
+        level,
+        Level {
+            name: "Dungeon".to_string(),
+            speed: 1.5,
+            lives: 3,
+            hidden: true,
+            tint: Color {
+                r: 255,
+                g: 0,
+                b: 0,
+                a: 128,
+            },
+            target: 12,
+            difficulty: Difficulty::Hard,
+            element: Element::Water,
+            stats: Stats {
+                strength: 7,
+                weapon: Weapon { damage: 2.5 },
+            },
+            music: None,
+            score: 0,
+        }
+    );
+}
+
+#[test]
+fn borrow_strings() {
+    #[derive(Deserialize)]
+    struct Name<'a> {
+        name: &'a str,
+    }
+
+    let map = load_map(PROPERTIES);
+    let name: Name = map.properties.as_ref().unwrap().deserialize_into().unwrap();
+
+    assert_eq!(name.name, "Dungeon");
+}
+
+#[test]
+fn missing_property() {
+    #[derive(Debug, Deserialize)]
+    struct Player {
+        #[allow(dead_code)]
+        health: u32,
+    }
+
+    let map = load_map(PROPERTIES);
+    let result = map
+        .properties
+        .as_ref()
+        .unwrap()
+        .deserialize_into::<Player>();
+
+    assert_eq!(
+        result.unwrap_err(),
+        PropertyError::Missing {
+            name: "health".to_string(),
+        }
+    );
+}
+
+#[test]
+fn errors_name_the_property() {
+    #[derive(Debug, Deserialize)]
+    struct Stats {
+        #[allow(dead_code)]
+        strength: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Level {
+        #[allow(dead_code)]
+        stats: Stats,
+    }
+
+    let map = load_map(PROPERTIES);
+    let error = map
+        .properties
+        .as_ref()
+        .unwrap()
+        .deserialize_into::<Level>()
+        .unwrap_err();
+
+    match error {
+        PropertyError::Deserialize(ref message) => {
+            assert!(
+                message.starts_with("property 'stats': property 'strength': "),
+                "unexpected message: {}",
+                message
+            );
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn unknown_enum_variant() {
+    #[derive(Debug, Deserialize)]
+    struct Level {
+        #[allow(dead_code)]
+        difficulty: Difficulty,
+    }
+
+    // by index, past the last variant
+    let map = load_map(r#"<property name="difficulty" type="int" value="5"/>"#);
+    let result = map.properties.as_ref().unwrap().deserialize_into::<Level>();
+    assert!(result.is_err());
+
+    // by name
+    let map = load_map(r#"<property name="difficulty" value="Nightmare"/>"#);
+    let result = map.properties.as_ref().unwrap().deserialize_into::<Level>();
+    match result.unwrap_err() {
+        PropertyError::Deserialize(ref message) => {
+            assert!(
+                message.contains("Nightmare"),
+                "unexpected message: {}",
+                message
+            )
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
@@ -0,0 +1,93 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{Map, Property, ValidationError};
+
+fn load_map() -> Map {
+    common::load_map(
+        r#"tiledversion="1.10.2" orientation="orthogonal" width="4" height="4" tilewidth="16" tileheight="16" nextobjectid="5""#,
+        r#"<properties>
+ <property name="boss" type="object" value="3"/>
+ <property name="exit" type="object" value=""/>
+</properties>
+<layer id="1" name="walls" width="4" height="4">
+ <properties>
+  <property name="door" type="object" value="99"/>
+ </properties>
+ <data encoding="csv"></data>
+</layer>
+<group id="2" name="level">
+ <objectgroup id="3" name="enemies">
+  <object id="3" name="dragon" x="16" y="16"/>
+  <object id="4" name="switch" x="32" y="16">
+   <properties>
+    <property name="guard" type="object" value="3"/>
+    <property name="wiring" type="class" propertytype="Wiring">
+     <properties>
+      <property name="target" type="object" value="42"/>
+     </properties>
+    </property>
+   </properties>
+  </object>
+ </objectgroup>
+</group>"#,
+    )
+}
+
+#[test]
+fn find_objects_in_groups() {
+    let map = load_map();
+
+    let (group, object) = map.object_by_id(3).unwrap();
+    assert_eq!(group.common.name, "enemies");
+    assert_eq!(object.name, Some("dragon".to_string()));
+
+    assert!(map.object_by_id(5).is_none());
+}
+
+#[test]
+fn resolve_object_properties() {
+    let map = load_map();
+    let properties = map.properties.as_ref().unwrap();
+
+    let boss = properties.get("boss").unwrap();
+    assert_eq!(*boss, Property::Object(3));
+    let (_, object) = map.resolve_object_property(boss).unwrap();
+    assert_eq!(object.name, Some("dragon".to_string()));
+
+    // an unset object property refers to no object
+    let exit = properties.get("exit").unwrap();
+    assert_eq!(*exit, Property::Object(0));
+    assert!(map.resolve_object_property(exit).is_none());
+
+    assert!(map.resolve_object_property(&Property::Object(42)).is_none());
+    assert!(map.resolve_object_property(&Property::Int(3)).is_none());
+}
+
+#[test]
+fn validate_reports_missing_objects() {
+    let map = load_map();
+    let errors = map.validate();
+
+    assert_eq!(
+        errors,
+        vec![
+            ValidationError::DanglingObjectReference {
+                location: "layer 'walls'".to_string(),
+                property: "door".to_string(),
+                id: 99,
+            },
+            ValidationError::DanglingObjectReference {
+                location: "object 4".to_string(),
+                property: "wiring.target".to_string(),
+                id: 42,
+            },
+        ]
+    );
+
+    assert_eq!(
+        errors[1].to_string(),
+        "property 'wiring.target' of object 4 refers to object 42, which does not exist"
+    );
+}
@@ -0,0 +1,185 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{Map, Object};
+
+fn load_map() -> Map {
+    common::load_map(
+        r#"orientation="orthogonal" width="100" height="100" tilewidth="10" tileheight="10" nextobjectid="6""#,
+        r#"<objectgroup id="1" name="objects">
+ <object id="1" name="crate" x="100" y="100" width="50" height="50"/>
+ <object id="2" name="pond" x="300" y="100" width="100" height="100">
+  <ellipse/>
+ </object>
+ <object id="3" name="fence" x="100" y="400">
+  <polyline points="0,0 100,0"/>
+ </object>
+ <object id="4" name="spawn" x="500" y="500">
+  <point/>
+ </object>
+ <object id="5" name="ramp" x="600" y="100">
+  <polygon points="0,0 100,0 0,100"/>
+ </object>
+</objectgroup>"#,
+    )
+}
+
+fn ids(objects: Vec<&Object>) -> Vec<u32> {
+    objects.iter().map(|o| o.id).collect()
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 0.01,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn query_point() {
+    let map = load_map();
+    let index = map.spatial_index(64.0);
+
+    assert_eq!(ids(index.query_point((120.0, 120.0))), vec![1]);
+    assert_eq!(ids(index.query_point((350.0, 150.0))), vec![2]);
+    assert_eq!(ids(index.query_point((610.0, 110.0))), vec![5]);
+
+    // inside of the bounds of the ellipse and the triangle, but not of their
+    // shapes
+    assert!(index.query_point((305.0, 105.0)).is_empty());
+    assert!(index.query_point((690.0, 190.0)).is_empty());
+
+    // points have no area
+    assert!(index.query_point((500.0, 500.0)).is_empty());
+}
+
+#[test]
+fn query_rect() {
+    let map = load_map();
+    let index = map.spatial_index(64.0);
+
+    // overlaps the crate and the edge of the pond, across several cells
+    assert_eq!(ids(index.query_rect(140.0, 140.0, 170.0, 20.0)), vec![1, 2]);
+
+    // lines and points are found, even though they have no area
+    assert_eq!(ids(index.query_rect(190.0, 390.0, 20.0, 20.0)), vec![3]);
+    assert_eq!(ids(index.query_rect(490.0, 490.0, 20.0, 20.0)), vec![4]);
+
+    // between the crate and the pond
+    assert!(index.query_rect(160.0, 100.0, 130.0, 10.0).is_empty());
+}
+
+#[test]
+fn ray_cast_through_cells() {
+    let map = load_map();
+    let index = map.spatial_index(64.0);
+
+    let hit = index
+        .ray_cast((0.0, 125.0), (1.0, 0.0), ::std::f32::INFINITY)
+        .unwrap();
+    assert_eq!(hit.object.id, 1);
+    assert_close(hit.distance, 100.0);
+    assert_eq!(hit.point, (100.0, 125.0));
+
+    // the direction doesn't have to be normalized
+    let hit = index
+        .ray_cast((160.0, 150.0), (5.0, 0.0), ::std::f32::INFINITY)
+        .unwrap();
+    assert_eq!(hit.object.id, 2);
+    assert_close(hit.distance, 140.0);
+
+    // the fence is hit, the spawn point never is
+    let hit = index.ray_cast((150.0, 300.0), (0.0, 1.0), 500.0).unwrap();
+    assert_eq!(hit.object.id, 3);
+    assert_close(hit.distance, 100.0);
+    assert!(index.ray_cast((500.0, 0.0), (0.0, 1.0), 1000.0).is_none());
+
+    // diagonally onto the top edge of the ramp
+    let hit = index.ray_cast((520.0, 0.0), (1.0, 1.0), 1000.0).unwrap();
+    assert_eq!(hit.object.id, 5);
+    assert_close(hit.distance, 100.0 * 2f32.sqrt());
+    assert_close(hit.point.0, 620.0);
+    assert_close(hit.point.1, 100.0);
+
+    // too short to reach the crate
+    assert!(index.ray_cast((0.0, 125.0), (1.0, 0.0), 50.0).is_none());
+}
+
+#[test]
+fn ray_cast_from_inside() {
+    let map = load_map();
+    let index = map.spatial_index(64.0);
+
+    let hit = index.ray_cast((120.0, 120.0), (1.0, 0.0), 10.0).unwrap();
+    assert_eq!(hit.object.id, 1);
+    assert_eq!(hit.distance, 0.0);
+    assert_eq!(hit.point, (120.0, 120.0));
+
+    let hit = index.ray_cast((350.0, 150.0), (0.0, -1.0), 10.0).unwrap();
+    assert_eq!(hit.object.id, 2);
+    assert_eq!(hit.distance, 0.0);
+}
+
+#[test]
+fn ray_cast_from_outside_of_the_cells() {
+    let map = load_map();
+    let index = map.spatial_index(64.0);
+
+    // far to the left and above of all objects
+    let hit = index
+        .ray_cast((-10000.0, 125.0), (1.0, 0.0), ::std::f32::INFINITY)
+        .unwrap();
+    assert_eq!(hit.object.id, 1);
+    assert_close(hit.distance, 10100.0);
+
+    let hit = index
+        .ray_cast((-300.0, -290.0), (1.0, 1.0), ::std::f32::INFINITY)
+        .unwrap();
+    assert_eq!(hit.object.id, 1);
+    assert_close(hit.point.0, 100.0);
+    assert_close(hit.point.1, 110.0);
+
+    // past all objects, and pointing away from them
+    assert!(index
+        .ray_cast((1000.0, 125.0), (1.0, 0.0), ::std::f32::INFINITY)
+        .is_none());
+    assert!(index
+        .ray_cast((0.0, 125.0), (-1.0, 0.0), ::std::f32::INFINITY)
+        .is_none());
+    assert!(index
+        .ray_cast((0.0, -100.0), (1.0, 0.0), ::std::f32::INFINITY)
+        .is_none());
+}
+
+#[test]
+fn offsets_of_groups_are_applied() {
+    let map = common::load_map(
+        r#"orientation="orthogonal" width="100" height="100" tilewidth="10" tileheight="10" nextobjectid="3""#,
+        r#"<group id="1" name="level" offsetx="100" offsety="50">
+  <objectgroup id="2" name="objects" offsetx="10" offsety="20">
+   <object id="1" name="crate" x="0" y="0" width="10" height="10"/>
+   <object id="2" name="pond" x="40" y="0" width="20" height="20">
+    <ellipse/>
+   </object>
+  </objectgroup>
+ </group>"#,
+    );
+    let index = map.spatial_index(64.0);
+
+    // the crate covers (110, 70) to (120, 80)
+    assert_eq!(ids(index.query_point((115.0, 75.0))), vec![1]);
+    assert!(index.query_point((5.0, 5.0)).is_empty());
+    assert_eq!(ids(index.query_point((160.0, 80.0))), vec![2]);
+
+    assert_eq!(ids(index.query_rect(100.0, 60.0, 15.0, 15.0)), vec![1]);
+    assert!(index.query_rect(0.0, 0.0, 15.0, 15.0).is_empty());
+
+    let hit = index
+        .ray_cast((0.0, 75.0), (1.0, 0.0), ::std::f32::INFINITY)
+        .unwrap();
+    assert_eq!(hit.object.id, 1);
+    assert_eq!(hit.point, (110.0, 75.0));
+}
@@ -0,0 +1,94 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{LoadOptions, LoadWarning, MapLayer, Project, Property};
+
+const PROJECT: &str = r#"{
+    "propertyTypes": [
+        {
+            "id": 1,
+            "name": "Chest",
+            "type": "class",
+            "useAs": ["object"],
+            "members": [
+                { "name": "capacity", "type": "int", "value": 4 },
+                { "name": "weight", "type": "int", "value": 1 }
+            ]
+        }
+    ]
+}"#;
+
+#[test]
+fn inherited_properties_by_precedence() {
+    let map_str = common::tmx(
+        r#"tiledversion="1.10.2" orientation="orthogonal" width="10" height="10" tilewidth="16" tileheight="16" nextobjectid="2""#,
+        r#"<tileset firstgid="1" name="chests" tilewidth="16" tileheight="16" tilecount="1" columns="1">
+  <image source="chests.png" width="16" height="16"/>
+  <tile id="0">
+   <properties>
+    <property name="kind" value="tile"/>
+    <property name="loot" value="gold"/>
+    <property name="weight" type="int" value="10"/>
+   </properties>
+  </tile>
+ </tileset>
+ <objectgroup id="1" name="objects">
+  <object id="1" template="chest.tx" gid="1" x="32" y="48">
+   <properties>
+    <property name="kind" value="object"/>
+   </properties>
+  </object>
+ </objectgroup>"#,
+    );
+
+    let options = LoadOptions {
+        project: Some(Project::load_from_str(PROJECT).unwrap()),
+        ..LoadOptions::default()
+    };
+    let mut map = tiled_loader::load_from_str_with(&map_str, &options).unwrap();
+    map.load_templates("assets");
+    assert!(map.warnings.is_empty(), "{:?}", map.warnings);
+
+    let object = match map.layers[0] {
+        MapLayer::ObjectGroup(ref group) => &group.objects[0],
+        _ => panic!("expected an object group"),
+    };
+    let properties = map.effective_properties(object);
+
+    // the object overrides its tile
+    assert_eq!(properties.get_str("kind"), Ok("object"));
+    // the template overrides the tile and the class
+    assert_eq!(properties.get("weight"), Some(&Property::Int(20)));
+    assert_eq!(properties.get("locked"), Some(&Property::Bool(true)));
+    // the tile overrides the class
+    assert_eq!(properties.get_str("loot"), Ok("gold"));
+    // the class of the template has the lowest precedence
+    assert_eq!(properties.get("capacity"), Some(&Property::Int(4)));
+    assert_eq!(properties.len(), 5);
+}
+
+#[test]
+fn missing_template_is_a_warning() {
+    let mut map = common::load_map(
+        r#"tiledversion="1.10.2" orientation="orthogonal" width="10" height="10" tilewidth="16" tileheight="16" nextobjectid="2""#,
+        r#"<objectgroup id="1" name="objects">
+  <object id="1" template="missing.tx" x="32" y="48"/>
+ </objectgroup>"#,
+    );
+    map.load_templates("assets");
+
+    match map.warnings[..] {
+        [LoadWarning::TemplateNotLoaded { ref template, .. }] => {
+            assert_eq!(template, "missing.tx")
+        }
+        ref warnings => panic!("unexpected warnings: {:?}", warnings),
+    }
+    assert!(map.templates.is_empty());
+
+    let object = match map.layers[0] {
+        MapLayer::ObjectGroup(ref group) => &group.objects[0],
+        _ => panic!("expected an object group"),
+    };
+    assert!(map.effective_properties(object).is_empty());
+}
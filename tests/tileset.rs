@@ -0,0 +1,83 @@
+extern crate tiled_loader;
+
+mod common;
+
+use common::load_map;
+use tiled_loader::GridOrientation;
+
+const ATTRIBUTES: &str = r#"orientation="isometric" renderorder="right-down" width="4" height="4" tilewidth="32" tileheight="16" nextobjectid="1""#;
+
+#[test]
+fn padded_tileset() {
+    let map = load_map(
+        ATTRIBUTES,
+        r#"<tileset firstgid="1" name="padded" tilewidth="16" tileheight="16" spacing="2" margin="1" tilecount="8" columns="4">
+  <tileoffset x="4" y="-8"/>
+  <grid orientation="isometric" width="32" height="16"/>
+  <image source="padded.png" width="73" height="37"/>
+ </tileset>"#,
+    );
+    let tileset = &map.tilesets[0];
+
+    assert_eq!((tileset.margin, tileset.spacing), (1, 2));
+    assert_eq!((tileset.tileoffset.x, tileset.tileoffset.y), (4, -8));
+
+    let grid = tileset.grid.as_ref().unwrap();
+    assert_eq!(grid.orientation, GridOrientation::Isometric);
+    assert_eq!((grid.width, grid.height), (32, 16));
+
+    // the margin is only around the image, the spacing between tiles
+    assert_eq!(tileset.tile_rect(0), Some((1, 1, 16, 16)));
+    assert_eq!(tileset.tile_rect(3), Some((55, 1, 16, 16)));
+    assert_eq!(tileset.tile_rect(5), Some((19, 19, 16, 16)));
+    assert_eq!(tileset.tile_rect(8), None);
+}
+
+#[test]
+fn defaults() {
+    let map = load_map(
+        ATTRIBUTES,
+        r#"<tileset firstgid="1" name="plain" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="plain.png" width="32" height="32"/>
+ </tileset>"#,
+    );
+    let tileset = &map.tilesets[0];
+
+    assert_eq!((tileset.margin, tileset.spacing), (0, 0));
+    assert_eq!((tileset.tileoffset.x, tileset.tileoffset.y), (0, 0));
+    assert!(tileset.grid.is_none());
+    assert_eq!(tileset.tile_rect(3), Some((16, 16, 16, 16)));
+}
+
+#[test]
+fn image_collection_with_sparse_ids() {
+    let map = load_map(
+        ATTRIBUTES,
+        r#"<tileset firstgid="1" name="props" tilewidth="64" tileheight="48" tilecount="3" columns="0">
+  <tile id="0">
+   <image width="32" height="32" source="barrel.png"/>
+  </tile>
+  <tile id="3">
+   <image width="64" height="48" source="cart.png"/>
+  </tile>
+  <tile id="7" x="16" y="8" width="20" height="24">
+   <image width="64" height="64" source="sheet.png"/>
+  </tile>
+ </tileset>"#,
+    );
+    let tileset = &map.tilesets[0];
+
+    assert!(tileset.is_image_collection());
+    assert_eq!(tileset.tile(3).unwrap().id, 3);
+    assert!(tileset.tile(1).is_none());
+
+    assert_eq!(tileset.tile_image(3).unwrap().source, "cart.png");
+    assert_eq!(tileset.tile_size(0), Some((32, 32)));
+    assert_eq!(tileset.tile_rect(3), Some((0, 0, 64, 48)));
+
+    // only part of the image is used
+    assert_eq!(tileset.tile_rect(7), Some((16, 8, 20, 24)));
+
+    assert_eq!(tileset.tile_rect(1), None);
+    assert!(tileset.tile_image(1).is_none());
+}
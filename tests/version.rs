@@ -0,0 +1,82 @@
+extern crate tiled_loader;
+
+mod common;
+
+use tiled_loader::{LoadOptions, LoadWarning, Version, VersionPolicy};
+
+fn tmx(tiledversion: Option<&str>) -> String {
+    let attributes =
+        r#"orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16""#;
+
+    common::tmx(
+        &match tiledversion {
+            Some(v) => format!(r#"tiledversion="{}" {}"#, v, attributes),
+            None => attributes.to_string(),
+        },
+        &common::tile_layer("ground", 2, 2, "0,0,0,0"),
+    )
+}
+
+fn load_options(old_versions: VersionPolicy, require_tiledversion: bool) -> LoadOptions {
+    LoadOptions {
+        minimum_version: Version::new(1, 2, 0),
+        old_versions: old_versions,
+        require_tiledversion: require_tiledversion,
+        ..LoadOptions::default()
+    }
+}
+
+#[test]
+fn parse_versions() {
+    assert_eq!("1.10.2".parse(), Ok(Version::new(1, 10, 2)));
+    assert_eq!("1.4".parse(), Ok(Version::new(1, 4, 0)));
+    assert_eq!("1.4.0-beta".parse(), Ok(Version::new(1, 4, 0)));
+    assert!("".parse::<Version>().is_err());
+    assert!("1.2.3.4".parse::<Version>().is_err());
+}
+
+#[test]
+fn refuse_old_versions() {
+    let options = load_options(VersionPolicy::Refuse, false);
+
+    assert!(tiled_loader::load_from_str_with(&tmx(Some("1.1.5")), &options).is_err());
+
+    let map = tiled_loader::load_from_str_with(&tmx(Some("1.2.0")), &options).unwrap();
+    assert_eq!(map.tiledversion, Some(Version::new(1, 2, 0)));
+    assert!(map.warnings.is_empty());
+}
+
+#[test]
+fn warn_about_old_versions() {
+    let options = load_options(VersionPolicy::Warn, false);
+    let map = tiled_loader::load_from_str_with(&tmx(Some("1.1.5")), &options).unwrap();
+
+    assert_eq!(
+        map.warnings,
+        vec![LoadWarning::OldVersion {
+            version: Version::new(1, 1, 5),
+            minimum: Version::new(1, 2, 0),
+        }]
+    );
+}
+
+#[test]
+fn missing_version_is_accepted_by_default() {
+    let map = tiled_loader::load_from_str(&tmx(None)).unwrap();
+    assert_eq!(map.tiledversion, None);
+    assert!(map.warnings.is_empty());
+
+    let options = load_options(VersionPolicy::Refuse, false);
+    let map = tiled_loader::load_from_str_with(&tmx(None), &options).unwrap();
+    assert!(map.warnings.is_empty());
+}
+
+#[test]
+fn missing_version_can_be_required() {
+    let options = load_options(VersionPolicy::Warn, true);
+    let map = tiled_loader::load_from_str_with(&tmx(None), &options).unwrap();
+    assert_eq!(map.warnings, vec![LoadWarning::MissingTiledVersion]);
+
+    let options = load_options(VersionPolicy::Refuse, true);
+    assert!(tiled_loader::load_from_str_with(&tmx(None), &options).is_err());
+}
@@ -0,0 +1,97 @@
+extern crate tiled_loader;
+
+mod common;
+
+use common::load_map;
+use tiled_loader::{Tileset, WangId, WangSetType};
+
+const ATTRIBUTES: &str = r#"orientation="orthogonal" renderorder="right-down" width="4" height="4" tilewidth="16" tileheight="16" nextobjectid="1""#;
+
+fn load_tileset(content: &str) -> Tileset {
+    let tileset = format!(
+        r#"<tileset firstgid="1" name="terrain" tilewidth="16" tileheight="16" tilecount="16" columns="4">
+  <image source="terrain.png" width="64" height="64"/>
+  {}
+ </tileset>"#,
+        content
+    );
+
+    load_map(ATTRIBUTES, &tileset).tilesets.remove(0)
+}
+
+#[test]
+fn find_tile_by_corners() {
+    let tileset = load_tileset(
+        r##"<wangsets>
+   <wangset name="ground" type="corner" tile="-1">
+    <wangcolor name="grass" color="#00ff00" tile="-1" probability="1"/>
+    <wangcolor name="sand" color="#ffff00" tile="-1" probability="0.5"/>
+    <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+    <wangtile tileid="1" wangid="0,1,0,2,0,1,0,1"/>
+    <wangtile tileid="2" wangid="0,2,0,2,0,2,0,2"/>
+   </wangset>
+  </wangsets>"##,
+    );
+    let wangset = &tileset.wangsets[0];
+
+    assert_eq!(wangset.type_, WangSetType::Corner);
+    assert_eq!(wangset.color(2).unwrap().name, "sand");
+    assert_eq!(wangset.color(2).unwrap().probability, 0.5);
+    assert!(wangset.color(0).is_none());
+
+    let wanted = WangId::from_corners([1, 2, 1, 1]);
+    assert_eq!(wangset.find_tile(wanted).unwrap().tileid, 1);
+    assert_eq!(wangset.tiles[1].wangid.corners(), [1, 2, 1, 1]);
+
+    // edges don't matter for corner sets
+    let mut with_edges = WangId::from_corners([2, 2, 2, 2]);
+    with_edges.0[WangId::TOP] = 1;
+    assert_eq!(wangset.find_tile(with_edges).unwrap().tileid, 2);
+
+    assert!(wangset
+        .find_tile(WangId::from_corners([1, 2, 1, 2]))
+        .is_none());
+}
+
+#[test]
+fn legacy_wang_set() {
+    // before tiled 1.5, edges and corners had separate colors, and ids were
+    // stored as one nibble per position
+    let tileset = load_tileset(
+        r##"<wangsets>
+   <wangset name="walls" tile="-1">
+    <wangedgecolor name="wall" color="#ff0000" tile="-1" probability="1"/>
+    <wangcornercolor name="floor" color="#0000ff" tile="-1" probability="1"/>
+    <wangtile tileid="5" wangid="0x10101011"/>
+   </wangset>
+  </wangsets>"##,
+    );
+    let wangset = &tileset.wangsets[0];
+
+    assert_eq!(wangset.type_, WangSetType::Mixed);
+    let names: Vec<&str> = wangset.colors.iter().map(|c| &c.name[..]).collect();
+    assert_eq!(names, vec!["wall", "floor"]);
+
+    // a wall at the top, floor at all corners: corner colors come after the
+    // edge colors
+    assert_eq!(wangset.tiles[0].wangid, WangId([1, 2, 0, 2, 0, 2, 0, 2]));
+}
+
+#[test]
+fn legacy_terrain() {
+    let tileset = load_tileset(
+        r#"<terraintypes>
+   <terrain name="grass" tile="0"/>
+   <terrain name="water" tile="3"/>
+  </terraintypes>
+  <tile id="2" terrain="0,,1,0"/>"#,
+    );
+
+    let names: Vec<&str> = tileset.terraintypes.iter().map(|t| &t.name[..]).collect();
+    assert_eq!(names, vec!["grass", "water"]);
+
+    let terrain = [Some(0), None, Some(1), Some(0)];
+    assert_eq!(tileset.tile(2).unwrap().terrain, Some(terrain));
+    assert_eq!(tileset.find_terrain_tile(&terrain), Some(2));
+    assert_eq!(tileset.find_terrain_tile(&[Some(1); 4]), None);
+}